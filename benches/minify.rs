@@ -0,0 +1,200 @@
+//! Throughput of the minifier on representative documents.
+//!
+//! Run with `cargo bench`. `minify_html_bytes` measures the minifier alone, `minify_response`
+//! adds buffering the body and the hop to the blocking thread pool the middleware takes. The
+//! `fragment` group compares the full pass on a small patch with the cheap pass it gets when
+//! marked as a fragment. The `scratch` group checks that a call on a thread with a warm scratch
+//! buffer allocates less than one on a fresh thread, then times `minify_in_place`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use axum::{
+    body::Body,
+    http::{header::CONTENT_TYPE, HeaderName, Response},
+};
+use axum_mini::{minify_html_bytes, minify_in_place, minify_response, MinifierConfig};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use http_body_util::BodyExt;
+
+/// The system allocator, counting every allocation and reallocation.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocations made by `f` on the current thread, assuming nothing else allocates meanwhile.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+const HEAD: &str = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <title>Benchmark</title>
+    <style>
+      body { margin: 0 auto; max-width: 60rem; font-family: system-ui, sans-serif; }
+      .card { padding: 1rem; border: 1px solid #DDDDDD; }
+    </style>
+  </head>
+  <body>
+"#;
+
+const ITEM: &str = r#"    <article class="card  featured">
+      <h2>   Article title   </h2>
+      <!-- teaser -->
+      <p>
+        Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor
+        incididunt ut labore et dolore magna aliqua. <a href="/more" title="Read more">More</a>
+      </p>
+      <ul>
+        <li>  First  </li>
+        <li>  Second  </li>
+      </ul>
+      <button type="button" onclick="toggle(this)">Toggle</button>
+    </article>
+"#;
+
+const TAIL: &str = r#"    <script>
+      function toggle(button) {
+        const card = button.closest('.card');
+        card.classList.toggle('open');
+      }
+    </script>
+  </body>
+</html>
+"#;
+
+/// A LiveView-style patch replacing one list item.
+const FRAGMENT: &str = r#"
+  <li id="todo-42" class="todo  done">
+    <input type="checkbox" checked>  <span>  Ship the release  </span>
+  </li>
+"#;
+
+/// Builds a document of roughly `size` bytes.
+fn document(size: usize) -> Vec<u8> {
+    let mut html = String::from(HEAD);
+    while html.len() + TAIL.len() < size {
+        html.push_str(ITEM);
+    }
+    html.push_str(TAIL);
+    html.into_bytes()
+}
+
+fn bench_minify(c: &mut Criterion) {
+    let cfg = MinifierConfig::default();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("failed to build the runtime");
+
+    let mut group = c.benchmark_group("minify");
+    for (name, size) in [("1KB", 1 << 10), ("100KB", 100 << 10), ("1MB", 1 << 20)] {
+        let html = document(size);
+        group.throughput(Throughput::Bytes(html.len() as u64));
+        group.bench_with_input(BenchmarkId::new("minify_html_bytes", name), &html, |b, html| {
+            b.iter(|| minify_html_bytes(html, &cfg));
+        });
+        group.bench_with_input(BenchmarkId::new("minify_response", name), &html, |b, html| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let response = Response::builder()
+                        .header(CONTENT_TYPE, "text/html")
+                        .body(Body::from(html.clone()))
+                        .unwrap();
+                    let response = minify_response(response, &cfg).await;
+                    response.into_body().collect().await.unwrap().to_bytes()
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_fragment(c: &mut Criterion) {
+    let marker = HeaderName::from_static("x-html-fragment");
+    let cfg = MinifierConfig::builder().fragment_header(marker.clone()).build();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .expect("failed to build the runtime");
+
+    let mut group = c.benchmark_group("fragment");
+    for (name, marked) in [("full", false), ("fragment", true)] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    let mut response = Response::builder().header(CONTENT_TYPE, "text/html");
+                    if marked {
+                        response = response.header(&marker, "1");
+                    }
+                    let response = response.body(Body::from(FRAGMENT)).unwrap();
+                    let response = minify_response(response, &cfg).await;
+                    response.into_body().collect().await.unwrap().to_bytes()
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_scratch(c: &mut Criterion) {
+    // The attribute passes are the ones writing into the scratch buffer
+    let cfg = MinifierConfig::aggressive();
+    let html = document(100 << 10);
+
+    let (cold, warm) = std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                let mut buf = html.clone();
+                let cold = allocations(|| minify_in_place(&mut buf, &cfg));
+                let mut buf = html.clone();
+                let warm = allocations(|| minify_in_place(&mut buf, &cfg));
+                (cold, warm)
+            })
+            .join()
+            .expect("counting thread panicked")
+    });
+    // `minify_html` allocates its own output either way, so only the scratch buffer is saved
+    assert!(warm < cold, "a warm scratch buffer made {warm} allocations, a cold one {cold}");
+
+    let mut group = c.benchmark_group("scratch");
+    group.throughput(Throughput::Bytes(html.len() as u64));
+    group.bench_function("minify_html_bytes", |b| b.iter(|| minify_html_bytes(&html, &cfg)));
+    let mut buf = Vec::with_capacity(html.len());
+    group.bench_function("minify_in_place", |b| {
+        b.iter(|| {
+            buf.clear();
+            buf.extend_from_slice(&html);
+            minify_in_place(&mut buf, &cfg);
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_minify, bench_fragment, bench_scratch);
+criterion_main!(benches);
@@ -0,0 +1,38 @@
+//! Minification of standalone CSS and JavaScript responses.
+//!
+//! The source is wrapped in a `<style>` or `<script>` element so that `minify_html` runs its
+//! own CSS and JS minifiers over it, then the wrapper is stripped again.
+
+use minify_html::Cfg;
+
+use crate::{engine, protect::find_ignore_case};
+
+/// Minifies a standalone stylesheet, returning it unchanged if that isn't possible.
+pub(crate) fn minify_css(input: &[u8], cfg: &Cfg) -> Vec<u8> {
+    minify_wrapped(input, "style", &engine::with_css(cfg))
+}
+
+/// Minifies a standalone script, returning it unchanged if that isn't possible.
+pub(crate) fn minify_js(input: &[u8], cfg: &Cfg) -> Vec<u8> {
+    minify_wrapped(input, "script", &engine::with_js(cfg))
+}
+
+fn minify_wrapped(input: &[u8], tag: &str, cfg: &Cfg) -> Vec<u8> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    // A closing tag inside the source would end the wrapper early
+    if find_ignore_case(input, &close.as_bytes()[..close.len() - 1], 0).is_some() {
+        return input.to_vec();
+    }
+
+    let mut document = Vec::with_capacity(open.len() + input.len() + close.len());
+    document.extend_from_slice(open.as_bytes());
+    document.extend_from_slice(input);
+    document.extend_from_slice(close.as_bytes());
+
+    let minified = engine::minify(&document, cfg);
+    minified
+        .strip_prefix(open.as_bytes())
+        .and_then(|rest| rest.strip_suffix(close.as_bytes()))
+        .map_or_else(|| input.to_vec(), <[u8]>::to_vec)
+}
@@ -0,0 +1,348 @@
+//! Rewrites of attribute values in minified HTML.
+//!
+//! Each pass appends the rewritten document to an output buffer, so that callers can reuse one.
+
+use std::{
+    ops::Range,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use minify_html::Cfg;
+
+use crate::{
+    content_type::JS_CONTENT_TYPES,
+    engine,
+    protect::{find, opens_tag, raw_text_end, tag_end},
+};
+
+/// An attribute of a start tag, as ranges into the document.
+pub(crate) struct Attribute {
+    pub(crate) name: Range<usize>,
+    /// The value without its quotes, if the attribute has one.
+    pub(crate) value: Option<Range<usize>>,
+    /// The whole attribute, including any quotes around the value.
+    pub(crate) span: Range<usize>,
+}
+
+/// Collapses runs of whitespace in `class` attribute values to single spaces and trims them.
+pub(crate) fn collapse_class_whitespace(html: &[u8], out: &mut Vec<u8>) {
+    rewrite_values(html, b"class", collapse_whitespace, out);
+}
+
+/// Compacts `style` attribute values: whitespace is collapsed and dropped around `:`, `;` and
+/// `,`, a trailing `;` is removed and hex colors are lowercased. Strings and `url(...)` are
+/// copied verbatim.
+pub(crate) fn minify_inline_styles(html: &[u8], out: &mut Vec<u8>) {
+    rewrite_values(html, b"style", compact_declarations, out);
+}
+
+/// Drops `type` attributes that restate the default, i.e. JavaScript MIME types on `<script>`
+/// and `text/css` on `<style>`. Other types, such as `module`, are kept.
+pub(crate) fn remove_redundant_type_attrs(html: &[u8], out: &mut Vec<u8>) {
+    out.reserve(html.len());
+    let mut flushed = 0;
+    for_each_start_tag(html, |lt, end| {
+        let defaults: &[&str] = if opens_tag(html, lt, "script") {
+            &JS_CONTENT_TYPES
+        } else if opens_tag(html, lt, "style") {
+            &["text/css"]
+        } else {
+            return;
+        };
+        for attr in attributes(html, lt, end) {
+            let Some(value) = attr.value else { continue };
+            let value = html[value].trim_ascii();
+            if html[attr.name].eq_ignore_ascii_case(b"type")
+                && defaults.iter().any(|default| value.eq_ignore_ascii_case(default.as_bytes()))
+            {
+                // Take the whitespace separating it from the previous attribute too
+                let start = attr.span.start - html[..attr.span.start].iter().rev().take_while(|b| b.is_ascii_whitespace()).count();
+                out.extend_from_slice(&html[flushed..start]);
+                flushed = attr.span.end;
+            }
+        }
+    });
+    out.extend_from_slice(&html[flushed..]);
+}
+
+/// Puts double quotes back around the attribute values the minifier left unquoted.
+pub(crate) fn quote_attribute_values(html: &[u8], out: &mut Vec<u8>) {
+    out.reserve(html.len());
+    let mut flushed = 0;
+    for_each_start_tag(html, |lt, end| {
+        for attr in attributes(html, lt, end) {
+            let Some(value) = attr.value else { continue };
+            let quoted = matches!(html[value.start - 1], b'"' | b'\'');
+            if quoted || value.is_empty() || html[value.clone()].contains(&b'"') {
+                continue;
+            }
+            out.extend_from_slice(&html[flushed..value.start]);
+            out.push(b'"');
+            out.extend_from_slice(&html[value.clone()]);
+            out.push(b'"');
+            flushed = value.end;
+        }
+    });
+    out.extend_from_slice(&html[flushed..]);
+}
+
+/// Minifies the JavaScript in quoted `on*` event-handler attributes.
+///
+/// Each handler is minified as the body of a function, so `return` keeps working. Handlers
+/// containing character references, and any the minifier can't handle cleanly, are left as
+/// they are.
+pub(crate) fn minify_event_handlers(html: &[u8], out: &mut Vec<u8>) {
+    let cfg = engine::js_only();
+    out.reserve(html.len());
+    let mut flushed = 0;
+    for_each_start_tag(html, |lt, end| {
+        for attr in attributes(html, lt, end) {
+            let Some(value) = attr.value else { continue };
+            let name = &html[attr.name];
+            if name.len() <= 2 || !name[..2].eq_ignore_ascii_case(b"on") {
+                continue;
+            }
+            // Only quoted values can hold whatever the JavaScript minifier produces
+            let quote = html[value.start - 1];
+            if !matches!(quote, b'"' | b'\'') || html.get(value.end) != Some(&quote) {
+                continue;
+            }
+            let Some(minified) = minify_handler(&html[value.clone()], quote, &cfg) else { continue };
+            out.extend_from_slice(&html[flushed..value.start]);
+            out.extend_from_slice(&minified);
+            flushed = value.end;
+        }
+    });
+    out.extend_from_slice(&html[flushed..]);
+}
+
+/// Minifies one handler, or returns `None` to keep it as is.
+fn minify_handler(handler: &[u8], quote: u8, cfg: &Cfg) -> Option<Vec<u8>> {
+    if handler.contains(&b'&') || handler.trim_ascii().is_empty() {
+        return None;
+    }
+    let mut script = b"<script>(function(){".to_vec();
+    script.extend_from_slice(handler);
+    script.extend_from_slice(b"\n})</script>");
+    // The JavaScript minifier panics on some malformed input
+    let minified = catch_unwind(AssertUnwindSafe(|| engine::minify(&script, cfg))).ok()?;
+    let function = minified.strip_prefix(b"<script>(()=>")?.strip_suffix(b")</script>")?;
+    let body = match function.strip_prefix(b"{") {
+        Some(block) => block.strip_suffix(b"}")?.to_vec(),
+        // A lone `return` becomes an expression body
+        None => [b"return ".as_slice(), function].concat(),
+    };
+    let unsafe_in_attribute = |i: usize| {
+        body[i] == quote || (body[i] == b'&' && body.get(i + 1).is_some_and(|b| b.is_ascii_alphanumeric() || *b == b'#'))
+    };
+    if (0..body.len()).any(unsafe_in_attribute) || body.len() >= handler.len() {
+        return None;
+    }
+    if !keeps_placeholders(handler, &body) {
+        return None;
+    }
+    Some(body)
+}
+
+/// Whether every template placeholder in `handler`, such as `{{ item.id }}` or `${id}`, is in
+/// `body` as written, rather than e.g. escaped after its string became a template literal.
+fn keeps_placeholders(handler: &[u8], body: &[u8]) -> bool {
+    let mut next = 0;
+    let mut searched = 0;
+    let opens = |i: usize| matches!(&handler[i..i + 2], b"{{" | b"${");
+    while let Some(start) = (next..handler.len().saturating_sub(1)).find(|&i| opens(i)) {
+        let close: &[u8] = if handler[start] == b'{' { b"}}" } else { b"}" };
+        let Some(end) = find(handler, close, start + 2).map(|at| at + close.len()) else { break };
+        let placeholder = &handler[start..end];
+        let mut from = searched;
+        loop {
+            let Some(at) = find(body, placeholder, from) else { return false };
+            if at == 0 || body[at - 1] != b'\\' {
+                searched = at + placeholder.len();
+                break;
+            }
+            from = at + 1;
+        }
+        next = end;
+    }
+    true
+}
+
+/// Replaces the value of every `name` attribute with `rewrite(value)`.
+fn rewrite_values(html: &[u8], name: &[u8], rewrite: impl Fn(&[u8]) -> Vec<u8>, out: &mut Vec<u8>) {
+    out.reserve(html.len());
+    let mut flushed = 0;
+    for_each_start_tag(html, |lt, end| {
+        for attr in attributes(html, lt, end) {
+            let Some(value) = attr.value else { continue };
+            if !html[attr.name].eq_ignore_ascii_case(name) {
+                continue;
+            }
+            let rewritten = rewrite(&html[value.clone()]);
+            if rewritten != html[value.clone()] {
+                out.extend_from_slice(&html[flushed..value.start]);
+                out.extend_from_slice(&rewritten);
+                flushed = value.end;
+            }
+        }
+    });
+    out.extend_from_slice(&html[flushed..]);
+}
+
+fn collapse_whitespace(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    for word in value.split(u8::is_ascii_whitespace).filter(|word| !word.is_empty()) {
+        if !out.is_empty() {
+            out.push(b' ');
+        }
+        out.extend_from_slice(word);
+    }
+    out
+}
+
+fn compact_declarations(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut space = false;
+    let mut i = 0;
+    while i < value.len() {
+        let byte = value[i];
+        if byte.is_ascii_whitespace() {
+            space = true;
+            i += 1;
+            continue;
+        }
+        if matches!(byte, b':' | b';' | b',') {
+            out.push(byte);
+            space = false;
+            i += 1;
+            continue;
+        }
+        if space && out.last().is_some_and(|last| !matches!(last, b':' | b';' | b',')) {
+            out.push(b' ');
+        }
+        space = false;
+        let end = if byte == b'"' || byte == b'\'' {
+            string_end(value, i)
+        } else if value[i..].len() >= 4 && value[i..i + 4].eq_ignore_ascii_case(b"url(") {
+            url_end(value, i)
+        } else if byte == b'#' {
+            let digits = value[i + 1..].iter().take_while(|b| b.is_ascii_hexdigit()).count();
+            let delimited = value.get(i + 1 + digits).is_none_or(|b| !b.is_ascii_alphanumeric() && *b != b'-' && *b != b'_');
+            if delimited && matches!(digits, 3 | 4 | 6 | 8) {
+                out.push(b'#');
+                out.extend(value[i + 1..i + 1 + digits].iter().map(u8::to_ascii_lowercase));
+                i += 1 + digits;
+                continue;
+            }
+            i + 1
+        } else {
+            i + 1
+        };
+        out.extend_from_slice(&value[i..end]);
+        i = end;
+    }
+    while out.last() == Some(&b';') {
+        out.pop();
+    }
+    out
+}
+
+/// Returns the index just past the CSS string starting at `start`.
+fn string_end(value: &[u8], start: usize) -> usize {
+    let quote = value[start];
+    let mut i = start + 1;
+    while i < value.len() {
+        match value[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    value.len()
+}
+
+/// Returns the index just past the `url(...)` starting at `start`.
+fn url_end(value: &[u8], start: usize) -> usize {
+    let mut i = start + 4;
+    while i < value.len() {
+        match value[i] {
+            b'"' | b'\'' => i = string_end(value, i),
+            b')' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    value.len()
+}
+
+/// Calls `f` with the start and end of every start tag outside comments and raw-text elements.
+pub(crate) fn for_each_start_tag(html: &[u8], mut f: impl FnMut(usize, usize)) {
+    let mut i = 0;
+    while let Some(lt) = find(html, b"<", i) {
+        if html[lt..].starts_with(b"<!--") {
+            i = find(html, b"-->", lt + 4).map_or(html.len(), |close| close + 3);
+        } else if !html.get(lt + 1).is_some_and(u8::is_ascii_alphabetic) {
+            i = lt + 1;
+        } else {
+            let Some(end) = tag_end(html, lt) else { break };
+            f(lt, end);
+            // The content of `<script>` and `<style>` isn't markup
+            i = raw_text_end(html, lt).unwrap_or(end);
+        }
+    }
+}
+
+/// Splits the start tag spanning `lt..end` into its attributes.
+pub(crate) fn attributes(html: &[u8], lt: usize, end: usize) -> Vec<Attribute> {
+    let is_space = |i: usize| html[i].is_ascii_whitespace();
+    let is_name_end = |i: usize| is_space(i) || matches!(html[i], b'=' | b'>' | b'/');
+    // The closing `>` isn't part of any attribute
+    let end = end - 1;
+    let mut attrs = Vec::new();
+    let mut i = lt + 1;
+    while i < end && !is_name_end(i) {
+        i += 1;
+    }
+    loop {
+        while i < end && (is_space(i) || html[i] == b'/') {
+            i += 1;
+        }
+        if i >= end {
+            return attrs;
+        }
+        let name_start = i;
+        i += 1;
+        while i < end && !is_name_end(i) {
+            i += 1;
+        }
+        let name = name_start..i;
+        while i < end && is_space(i) {
+            i += 1;
+        }
+        if i >= end || html[i] != b'=' {
+            let span = name.clone();
+            attrs.push(Attribute { name, value: None, span });
+            continue;
+        }
+        i += 1;
+        while i < end && is_space(i) {
+            i += 1;
+        }
+        let value = match html.get(i) {
+            Some(&quote) if i < end && (quote == b'"' || quote == b'\'') => {
+                let close = html[i + 1..end].iter().position(|&b| b == quote).map_or(end, |at| i + 1 + at);
+                let value = i + 1..close;
+                i = (close + 1).min(end);
+                value
+            }
+            _ => {
+                let start = i;
+                while i < end && !is_space(i) {
+                    i += 1;
+                }
+                start..i
+            }
+        };
+        let span = name.start..i;
+        attrs.push(Attribute { name, value: Some(value), span });
+    }
+}
@@ -0,0 +1,55 @@
+//! Body types used when a response is forwarded without being fully buffered.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::body::{Bytes, HttpBody};
+use http_body::{Frame, SizeHint};
+
+/// Body that first yields frames already read from `inner`, then the rest of `inner`.
+pub(crate) struct ReplayBody<B> {
+    buffered: VecDeque<Frame<Bytes>>,
+    inner: B,
+}
+
+impl<B> ReplayBody<B> {
+    pub(crate) fn new(buffered: impl Into<VecDeque<Frame<Bytes>>>, inner: B) -> Self {
+        Self {
+            buffered: buffered.into(),
+            inner,
+        }
+    }
+}
+
+impl<B> HttpBody for ReplayBody<B>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, B::Error>>> {
+        if let Some(frame) = self.buffered.pop_front() {
+            return Poll::Ready(Some(Ok(frame)));
+        }
+        Pin::new(&mut self.inner).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.buffered.is_empty() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let buffered: u64 = self.buffered.iter().filter_map(Frame::data_ref).map(|chunk| chunk.len() as u64).sum();
+        let inner = self.inner.size_hint();
+        let mut hint = SizeHint::new();
+        hint.set_lower(inner.lower() + buffered);
+        if let Some(upper) = inner.upper() {
+            hint.set_upper(upper + buffered);
+        }
+        hint
+    }
+}
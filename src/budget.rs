@@ -0,0 +1,71 @@
+//! Token bucket bounding the time spent minifying.
+
+use std::{
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Minification time allowed per window, shared by every request that uses it.
+///
+/// Share one budget by putting it in [`MinifierConfig::cpu_budget`](crate::MinifierConfig::cpu_budget).
+/// It works as a token bucket: it starts full, every minification takes the time it ran from
+/// it, and it refills at `budget` per `window`, up to `budget`. While it is empty, bodies are
+/// sent unminified. A minification already started always runs to completion and is charged
+/// in full, so the bucket can go into debt, which is repaid before the next body is minified.
+#[derive(Debug)]
+pub struct CpuBudget {
+    budget: Duration,
+    window: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Seconds of minification left; negative while in debt.
+    available: f64,
+    refilled: Instant,
+}
+
+impl CpuBudget {
+    /// Creates a budget allowing `budget` of minification time every `window`.
+    pub fn new(budget: Duration, window: Duration) -> Self {
+        Self {
+            budget,
+            window,
+            bucket: Mutex::new(Bucket {
+                available: budget.as_secs_f64(),
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Minification time left before bodies start passing through unminified.
+    pub fn remaining(&self) -> Duration {
+        Duration::from_secs_f64(self.refill(Duration::ZERO).max(0.0))
+    }
+
+    /// Whether there is any time left to start a minification.
+    pub(crate) fn has_budget(&self) -> bool {
+        self.refill(Duration::ZERO) > 0.0
+    }
+
+    /// Takes the time a minification ran from the bucket.
+    pub(crate) fn spend(&self, elapsed: Duration) {
+        self.refill(elapsed);
+    }
+
+    /// Adds the time earned since the last refill, takes `spent`, and returns what is left.
+    fn refill(&self, spent: Duration) -> f64 {
+        let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let budget = self.budget.as_secs_f64();
+        let earned = if self.window.is_zero() {
+            f64::INFINITY
+        } else {
+            budget * now.duration_since(bucket.refilled).as_secs_f64() / self.window.as_secs_f64()
+        };
+        bucket.available = (bucket.available + earned).min(budget) - spent.as_secs_f64();
+        bucket.refilled = now;
+        bucket.available
+    }
+}
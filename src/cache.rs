@@ -0,0 +1,128 @@
+//! In-memory cache of minified bodies.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, PoisonError},
+};
+
+use axum::body::Bytes;
+
+use crate::{content_type::ContentTypeClass, headers::content_hash};
+
+/// Bounded least-recently-used cache mapping response bodies to their minified form.
+///
+/// Share one cache between requests by putting it in [`MinifierConfig::cache`](crate::MinifierConfig::cache).
+/// Entries are keyed by a hash of the original body and of the settings that shape the output,
+/// so layers with different configs, and requests with a
+/// [`MinifierOverride`](crate::MinifierOverride), can share one cache. A hit is only used if
+/// the stored original matches byte for byte. Evicting the least recently used entry scans
+/// the whole cache, so keep the capacity modest. [`hits`](Self::hits) and
+/// [`misses`](Self::misses) tell how well it works.
+pub struct MinifyCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+}
+
+/// The document's class, the fingerprint of the settings and the hash of the original body.
+type CacheKey = (ContentTypeClass, u64, u64);
+
+#[derive(Default)]
+struct CacheInner {
+    entries: HashMap<CacheKey, CacheEntry>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+struct CacheEntry {
+    original: Bytes,
+    minified: Bytes,
+    last_used: u64,
+}
+
+impl MinifyCache {
+    /// Creates a cache holding at most `capacity` minified bodies.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(CacheInner::default()),
+        }
+    }
+
+    /// Number of cached bodies.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether the cache holds no bodies.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups answered from the cache, sparing a minification.
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Number of lookups that found nothing, so the body was minified.
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    pub(crate) fn get(&self, class: ContentTypeClass, settings: u64, original: &Bytes) -> Option<Bytes> {
+        let mut inner = self.lock();
+        inner.tick += 1;
+        let tick = inner.tick;
+        let hit = inner
+            .entries
+            .get_mut(&(class, settings, content_hash(original)))
+            .filter(|entry| entry.original == *original)
+            .map(|entry| {
+                entry.last_used = tick;
+                entry.minified.clone()
+            });
+        if hit.is_some() {
+            inner.hits += 1;
+        } else {
+            inner.misses += 1;
+        }
+        hit
+    }
+
+    pub(crate) fn insert(&self, class: ContentTypeClass, settings: u64, original: Bytes, minified: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = (class, settings, content_hash(&original));
+        let mut inner = self.lock();
+        if inner.entries.len() >= self.capacity && !inner.entries.contains_key(&key) {
+            let oldest = inner.entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                inner.entries.remove(&oldest);
+            }
+        }
+        inner.tick += 1;
+        let last_used = inner.tick;
+        inner.entries.insert(key, CacheEntry { original, minified, last_used });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheInner> {
+        // The cache holds no invariants a panicking holder could break
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for MinifyCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MinifyCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
@@ -0,0 +1,36 @@
+//! Transcoding of bodies in legacy charsets, so they can be minified as UTF-8.
+
+use axum::http::HeaderMap;
+use encoding_rs::{Encoding, UTF_8};
+
+use crate::content_type::content_type;
+
+/// Looks up the charset declared in `Content-Type`.
+///
+/// Returns `Ok(None)` for UTF-8 or no charset at all, and `Err(())` for charsets that are
+/// unknown or can't be written back, such as UTF-16.
+pub(crate) fn declared(headers: &HeaderMap) -> Result<Option<&'static Encoding>, ()> {
+    let Some(label) = content_type(headers).and_then(|ty| ty.get_param(mime::CHARSET).map(|c| c.to_string())) else {
+        return Ok(None);
+    };
+    match Encoding::for_label(label.as_bytes()) {
+        Some(encoding) if encoding == UTF_8 => Ok(None),
+        Some(encoding) if encoding.output_encoding() == encoding => Ok(Some(encoding)),
+        _ => Err(()),
+    }
+}
+
+/// Converts `input` to UTF-8, or `None` if it isn't valid in `encoding`.
+pub(crate) fn decode(encoding: &'static Encoding, input: &[u8]) -> Option<Vec<u8>> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(input)
+        .map(|text| text.into_owned().into_bytes())
+}
+
+/// Converts minified UTF-8 back to `encoding`, or `None` if it has characters `encoding`
+/// can't represent.
+pub(crate) fn encode(encoding: &'static Encoding, utf8: &[u8]) -> Option<Vec<u8>> {
+    let text = std::str::from_utf8(utf8).ok()?;
+    let (bytes, _, unmappable) = encoding.encode(text);
+    (!unmappable).then(|| bytes.into_owned())
+}
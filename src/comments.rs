@@ -0,0 +1,61 @@
+//! Important `/*! ... */` comments in scripts, such as license banners, which the JavaScript
+//! minifier drops.
+
+use crate::{
+    attrs::for_each_start_tag,
+    protect::{find, find_ignore_case, opens_tag},
+};
+
+/// The important comments of every `<script>` element in `html`, in document order.
+pub(crate) fn in_scripts(html: &[u8]) -> Vec<Vec<&[u8]>> {
+    scripts(html)
+        .into_iter()
+        .map(|(start, end)| {
+            let mut comments = Vec::new();
+            let mut i = start;
+            while let Some(open) = find(&html[..end], b"/*!", i) {
+                let Some(close) = find(&html[..end], b"*/", open + 3) else { break };
+                comments.push(&html[open..close + 2]);
+                i = close + 2;
+            }
+            comments
+        })
+        .collect()
+}
+
+/// Puts the comments found by [`in_scripts`] back at the start of each script in `minified`.
+///
+/// Comments the script still contains, e.g. because it was protected, aren't added twice.
+/// Returns `None` if the scripts of `minified` don't line up with the original's.
+pub(crate) fn restore(minified: &[u8], comments: &[Vec<&[u8]>]) -> Option<Vec<u8>> {
+    let scripts = scripts(minified);
+    if scripts.len() != comments.len() {
+        return None;
+    }
+    let mut out = Vec::with_capacity(minified.len() + comments.iter().flatten().map(|c| c.len() + 1).sum::<usize>());
+    let mut flushed = 0;
+    for ((start, end), comments) in scripts.into_iter().zip(comments) {
+        out.extend_from_slice(&minified[flushed..start]);
+        for comment in comments {
+            if find(&minified[start..end], comment, 0).is_none() {
+                out.extend_from_slice(comment);
+                out.push(b'\n');
+            }
+        }
+        flushed = start;
+    }
+    out.extend_from_slice(&minified[flushed..]);
+    Some(out)
+}
+
+/// The content ranges of the `<script>` elements in `html`.
+fn scripts(html: &[u8]) -> Vec<(usize, usize)> {
+    let mut scripts = Vec::new();
+    for_each_start_tag(html, |lt, end| {
+        if opens_tag(html, lt, "script") {
+            let close = find_ignore_case(html, b"</script", end).unwrap_or(html.len());
+            scripts.push((end, close));
+        }
+    });
+    scripts
+}
@@ -0,0 +1,76 @@
+//! Compression of minified bodies, negotiated from the request's `Accept-Encoding`.
+
+use std::io::Write;
+
+use axum::http::{header::ACCEPT_ENCODING, HeaderMap, HeaderValue};
+
+/// Brotli quality; high enough to beat gzip, low enough to run on every response.
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+/// A content coding the client accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// Picks the coding to use for a request: brotli if acceptable, else gzip, else none.
+    pub(crate) fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let mut brotli = None;
+        let mut gzip = None;
+        let mut wildcard = None;
+        let codings = headers
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','));
+        for coding in codings {
+            let mut params = coding.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            let acceptable = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .all(|q| q.trim().parse::<f32>().is_ok_and(|q| q > 0.0));
+            if name.eq_ignore_ascii_case("br") {
+                brotli = Some(acceptable);
+            } else if name.eq_ignore_ascii_case("gzip") || name.eq_ignore_ascii_case("x-gzip") {
+                gzip = Some(acceptable);
+            } else if name == "*" {
+                wildcard = Some(acceptable);
+            }
+        }
+        // Codings the client didn't list are acceptable only through `*`
+        if brotli.or(wildcard) == Some(true) {
+            Some(Self::Brotli)
+        } else if gzip.or(wildcard) == Some(true) {
+            Some(Self::Gzip)
+        } else {
+            None
+        }
+    }
+
+    /// The `Content-Encoding` value for this coding.
+    pub(crate) fn header_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            Self::Brotli => "br",
+            Self::Gzip => "gzip",
+        })
+    }
+
+    /// Compresses `body` with this coding.
+    pub(crate) fn compress(self, body: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Brotli => {
+                let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+                writer.write_all(body).expect("writing to a Vec cannot fail");
+                writer.into_inner()
+            }
+            Self::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body).expect("writing to a Vec cannot fail");
+                encoder.finish().expect("writing to a Vec cannot fail")
+            }
+        }
+    }
+}
@@ -0,0 +1,2240 @@
+//! Configuration for the HTML minifier middleware.
+
+#[cfg(feature = "cache")]
+use std::hash::{Hash, Hasher};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use bytes::Bytes;
+use http::{response::Parts, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use minify_html::Cfg;
+#[cfg(feature = "axum")]
+use tokio::sync::Semaphore;
+
+#[cfg(feature = "axum")]
+use crate::CpuBudget;
+use crate::{engine, ConfigError, ContentTypeClass, MinifyStatsCollector, SkipReason};
+#[cfg(feature = "cache")]
+use crate::MinifyCache;
+
+/// Function minifying an HTML document with the given `minify_html` settings.
+pub type MinifyFn = Arc<dyn Fn(&[u8], &Cfg) -> Vec<u8> + Send + Sync>;
+
+/// Function rewriting an HTML body before it is minified.
+pub type PreProcessFn = Arc<dyn Fn(Bytes) -> Bytes + Send + Sync>;
+
+/// Predicate deciding from a buffered response's head and body to send it unminified.
+pub type SkipFn = Arc<dyn Fn(&Parts, &[u8]) -> bool + Send + Sync>;
+
+/// Callback told why a body the middleware would have minified was sent unminified.
+pub type OnSkipFn = Arc<dyn Fn(SkipReason) + Send + Sync>;
+
+/// Function rewriting a minified body, with access to the response headers.
+pub type PostProcessFn = Arc<dyn Fn(Bytes, &mut HeaderMap) -> Bytes + Send + Sync>;
+
+/// Function storing an inline stylesheet's CSS and returning the URL to link it from.
+pub type ExternalizeStylesFn = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// How HTML bodies are processed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MinifyMode {
+    /// Buffer the whole body and fully minify it.
+    #[default]
+    Buffered,
+    /// Collapse whitespace as the body streams through, without buffering it. Saves less,
+    /// but keeps memory flat and doesn't delay the first byte. The whitespace of regions the
+    /// config preserves, such as its `preserve_whitespace_tags`, is kept, as in full
+    /// minification.
+    Streaming,
+    /// Like [`Streaming`](Self::Streaming), but parsing the document with `lol_html`, so that
+    /// only text a browser renders as such is touched. See
+    /// [`LolHtmlMinifier`](crate::LolHtmlMinifier).
+    #[cfg(feature = "lol_html")]
+    LolHtml,
+}
+
+/// Settings controlling how HTML responses are minified.
+///
+/// `MinifierConfig::default()` matches the preset used by [`html_minifier`](crate::html_minifier);
+/// [`MinifierConfig::conservative`] and [`MinifierConfig::aggressive`] trade savings for safety
+/// in either direction.
+#[derive(Clone)]
+pub struct MinifierConfig {
+    /// Whether HTML is buffered and fully minified or streamed. Defaults to buffered.
+    pub mode: MinifyMode,
+    /// Runtime switch; while it reads `false`, responses pass through untouched. Defaults to
+    /// `None` (always enabled).
+    pub enabled: Option<Arc<AtomicBool>>,
+    /// Graceful-shutdown flag; once it reads `true`, no new minification is started and
+    /// responses pass through untouched, while minifications already running finish. Unlike
+    /// [`enabled`](Self::enabled), it is also checked just before a buffered body is handed to
+    /// the blocking pool. Defaults to `None`.
+    pub shutdown: Option<Arc<AtomicBool>>,
+    /// Minify and record [`MinifyStats`](crate::MinifyStats) as usual, but send the original
+    /// body, without the debug or preload headers a minified one would get. Defaults to
+    /// `false`.
+    pub dry_run: bool,
+    /// Fraction of responses to minify, from `0.0` to `1.0`; the rest pass through untouched,
+    /// e.g. for a canary rollout. Defaults to `1.0`.
+    pub sample_rate: f64,
+    /// Fraction of minified HTML bodies, from `0.0` to `1.0`, to check against the original:
+    /// if any visible text went missing or changed, the original is sent instead and the
+    /// mismatch is logged as an error with the `tracing` feature. Scanning both documents
+    /// again is costly, so this is meant for staging or a canary. Defaults to `0.0`.
+    pub verify_rate: f64,
+    /// Report the HTML body length before and after minification in `X-Original-Bytes` and
+    /// `X-Minified-Bytes` response headers. Meant for development. Defaults to `false`.
+    pub debug_headers: bool,
+    /// Minify CSS in `<style>` tags and `style` attributes. Defaults to `true`.
+    pub minify_css: bool,
+    /// Minify JavaScript in `<script>` tags. Defaults to `true`.
+    pub minify_js: bool,
+    /// Keep all HTML comments. Defaults to `false`.
+    pub keep_comments: bool,
+    /// Keep comments whose text starts with one of these prefixes, e.g. `"[if"` for IE
+    /// conditional comments or `"!"` for license banners. Defaults to none.
+    pub preserve_comment_prefixes: Vec<String>,
+    /// Keep `/*! ... */` comments, such as license banners, in minified `<script>` elements,
+    /// moved to the start of the script; HTML comments are still removed. The CSS minifier
+    /// keeps them in `<style>` already. Defaults to `false`.
+    pub keep_important_comments: bool,
+    /// Keep the content of `<pre>`, `<textarea>` and `<code>` elements byte for byte.
+    /// Defaults to `false`, relying on `minify_html`'s own handling.
+    pub preserve_sensitive_whitespace: bool,
+    /// Further elements whose content is kept byte for byte, such as custom elements that
+    /// render their text verbatim. Attributes on the tags are still minified. Defaults to empty.
+    pub preserve_whitespace_tags: Vec<String>,
+    /// Leave everything between `<!--minify:off-->` and `<!--minify:on-->` exactly as written.
+    /// Defaults to `false`.
+    pub honor_no_minify_markers: bool,
+    /// Keep `nonce` attributes exactly as written, quotes included. `minify_html` keeps nonce
+    /// values anyway, but drops empty ones and may unquote or move the rest, which matters to
+    /// anything filling them in or matching them after minification. Defaults to `false`.
+    pub preserve_nonces: bool,
+    /// Keep `data:` URIs exactly as written, both as attribute values such as `src` and inside
+    /// CSS `url(...)`. The minifier may otherwise unquote them or rewrite their spacing, which
+    /// breaks some hand-written SVG and base64 payloads. Defaults to `false`.
+    pub preserve_data_uris: bool,
+    /// Let `minify_html` shorten boolean attributes such as `disabled="disabled"` to
+    /// `disabled`. Turn it off for consumers that parse the output as XHTML. Defaults to
+    /// `true`.
+    pub collapse_boolean_attributes: bool,
+    /// Remove all bangs (`<!...>`). Defaults to `true`.
+    pub remove_bangs: bool,
+    /// Remove all processing instructions (`<?...?>`). Defaults to `true`.
+    pub remove_processing_instructions: bool,
+    /// Remove spaces between attributes when possible. Defaults to `true`.
+    pub allow_removing_spaces_between_attributes: bool,
+    /// Omit closing tags HTML allows to leave out, such as `</li>` and `</p>`. Turn it off for
+    /// consumers that match on the markup as text. Defaults to `true`.
+    pub remove_optional_closing_tags: bool,
+    /// Leave attribute values unquoted where HTML allows it, e.g. `class=lead`. When off,
+    /// every value is written in double quotes, also with
+    /// [`minify_html_cfg`](Self::minify_html_cfg). Defaults to `true`.
+    pub remove_attribute_quotes: bool,
+    /// Treat bodies that don't start with a doctype or `<html>` as fragments, such as htmx or
+    /// Turbo partials, and keep their closing tags so they splice into a page as written. Also
+    /// applies with [`minify_html_cfg`](Self::minify_html_cfg). Defaults to `false`.
+    pub fragment_safe: bool,
+    /// Only apply transformations that keep AMP pages valid: closing tags and the `<html>`
+    /// and `<head>` start tags are kept, inline scripts and attribute spacing are left alone,
+    /// and the `<style amp-boilerplate>` CSS, which the AMP validator requires verbatim, is
+    /// not touched. `<style amp-custom>` is still minified. Also applies with
+    /// [`minify_html_cfg`](Self::minify_html_cfg). Defaults to `false`.
+    pub amp_mode: bool,
+    /// End minified HTML with exactly one `\n` if the original ended with a newline, for
+    /// tooling that expects files to. Defaults to `false`.
+    pub preserve_trailing_newline: bool,
+    /// `minify_html` settings to use verbatim, for full control over the underlying minifier.
+    /// When set, `minify_css`, `minify_js`, `keep_comments`, `remove_bangs`,
+    /// `remove_processing_instructions`, `allow_removing_spaces_between_attributes` and
+    /// `remove_optional_closing_tags` are ignored. Defaults to `None`.
+    pub minify_html_cfg: Option<Cfg>,
+    /// Trim `class` attribute values and collapse whitespace runs inside them to one space.
+    /// Defaults to `false`.
+    pub collapse_class_whitespace: bool,
+    /// Compact `style` attribute values with a lightweight pass of its own, which also covers
+    /// values the CSS minifier left alone (or all of them, with `minify_css` off). Defaults to
+    /// `false`.
+    pub minify_inline_styles: bool,
+    /// Drop `type` attributes restating the default on `<script>` (JavaScript) and `<style>`
+    /// (`text/css`). `minify_html` usually does this already; this guarantees it, e.g. with a
+    /// custom [`minify_fn`](Self::minify_fn). Defaults to `false`.
+    pub remove_redundant_type_attrs: bool,
+    /// Minify the JavaScript in `on*` event-handler attributes, which `minify_html` leaves
+    /// alone even with `minify_js`. Off by default because handlers are often generated by
+    /// templates; handlers whose `{{ ... }}` or `${...}` placeholders would come out changed
+    /// are kept as written. Defaults to `false`.
+    pub minify_inline_event_handlers: bool,
+    /// Minify the markup inside `<noscript>` elements, which `minify_html` parses as HTML.
+    /// Turn off to send fallbacks exactly as written. Defaults to `true`.
+    pub minify_noscript: bool,
+    /// Responses smaller than this many bytes are forwarded unchanged. Defaults to `0`.
+    pub min_size_bytes: usize,
+    /// Called with each buffered response of a minifiable type; returning `true` sends its body
+    /// unminified. For rules the other settings can't express. Request bodies are passed
+    /// with the parts of a `200 OK` response carrying the request's headers. Defaults to
+    /// `None`.
+    pub skip_if: Option<SkipFn>,
+    /// Skip bodies that already look minified: those where newlines and runs of whitespace
+    /// make up less than this fraction of the bytes, e.g. `0.01`. Defaults to `None` (always
+    /// minify).
+    pub skip_minified_below: Option<f64>,
+    /// Largest `minified / original` length ratio worth sending, from `0.0` to `1.0`;
+    /// minified bodies that saved less are sent as they were, e.g. `0.95` to require at least
+    /// 5% savings. Defaults to `1.0` (always send the minified body).
+    pub min_savings_ratio: f64,
+    /// Content types treated as HTML, compared case-insensitively against the MIME type of
+    /// the response's `Content-Type` (ignoring parameters such as `charset`). Defaults to
+    /// `["text/html"]`.
+    pub content_types: Vec<String>,
+    /// Also treat types with a `+html` structured suffix, such as `application/vnd.acme+html`,
+    /// as HTML. Defaults to `false`.
+    pub minify_html_suffix_types: bool,
+    /// Treat responses without a `Content-Type` as HTML if the body starts with
+    /// `<!doctype html` or `<html`. Defaults to `false`.
+    pub sniff_html: bool,
+    /// Treat responses whose `Content-Type` is empty or only whitespace, as some buggy
+    /// upstreams send, as HTML. Values that aren't valid ASCII, and wildcards such as `*/*`,
+    /// are never guessed at. Defaults to `false`.
+    pub default_to_html_when_empty: bool,
+    /// Settings replacing these ones for particular kinds of document, e.g. to keep comments
+    /// in HTML but not in CSS. They apply once a response has been classified, so request
+    /// and status checks, and `per_content_type` of the entries themselves, are ignored.
+    /// Defaults to empty.
+    pub per_content_type: HashMap<ContentTypeClass, MinifierConfig>,
+    /// Responses larger than this many bytes are streamed through unminified instead of
+    /// being buffered. Defaults to `None` (unlimited).
+    pub max_body_bytes: Option<usize>,
+    /// Fail bodies over `max_body_bytes` with [`MinifyError::TooLarge`](crate::MinifyError::TooLarge)
+    /// (a `413`) instead of forwarding them unminified. Defaults to `false`.
+    pub reject_too_large: bool,
+    /// HTML bodies larger than this many bytes only get their whitespace collapsed, as in
+    /// [`MinifyMode::Streaming`], instead of full minification with CSS and JS. This bounds
+    /// the time spent on huge pages while still saving bytes. Defaults to `None` (always
+    /// minify fully).
+    pub full_minify_max_bytes: Option<usize>,
+    /// Response header a handler sets to mark its body as a small HTML fragment, such as a
+    /// LiveView-style patch. Fragments of up to [`fragment_max_bytes`](Self::fragment_max_bytes)
+    /// only get their whitespace collapsed and trimmed, in place of the full pass and the hop
+    /// to the blocking thread pool. The header is removed from the response. Defaults to `None`.
+    pub fragment_header: Option<HeaderName>,
+    /// Content types, such as `text/vnd.turbo-stream.html`, whose bodies are always HTML
+    /// fragments, as with [`fragment_header`](Self::fragment_header). Defaults to empty.
+    pub fragment_content_types: Vec<String>,
+    /// Largest fragment, in bytes, that gets the cheap pass; larger ones are fully minified.
+    /// Defaults to `4096`.
+    pub fragment_max_bytes: usize,
+    /// Forward bodies whose length disagrees with their `Content-Length` header unminified,
+    /// as a sign of a truncated or corrupt upstream response. Defaults to `false`.
+    pub strict_content_length: bool,
+    /// Leave the `Content-Length` of bodies the middleware rewrites to the server or an outer
+    /// layer: any value the response came with is removed and none is set. By default the
+    /// middleware replaces every `Content-Length` value with exactly one for the body as sent.
+    /// Defaults to `false`.
+    pub defer_content_length: bool,
+    /// Status of a response sent in place of [`MinifyError::BodyRead`](crate::MinifyError::BodyRead)
+    /// when reading a body fails. Defaults to `None`: the error is returned, and renders as a
+    /// `500 Internal Server Error` with its own description.
+    pub read_error_status: Option<StatusCode>,
+    /// Body of a response sent in place of [`MinifyError::BodyRead`](crate::MinifyError::BodyRead),
+    /// e.g. a generic message so that the underlying error isn't exposed to clients. Without
+    /// a [`read_error_status`](Self::read_error_status) it is sent as a
+    /// `500 Internal Server Error`. Defaults to `None` (the error is returned).
+    pub read_error_message: Option<String>,
+    /// Header a handler (or client, on the request) can set to `off` or `false` to skip
+    /// minification. It is removed from the response and added to its `Vary` header.
+    /// Defaults to `X-Minify`.
+    pub opt_out_header: Option<HeaderName>,
+    /// Query parameter a client can set to `0`, `off` or `false`, as in `?minify=0`, to get
+    /// the page unminified, e.g. for debugging in production. Defaults to `minify`; `None`
+    /// disables it.
+    pub opt_out_query: Option<String>,
+    /// Requests whose `User-Agent` contains one of these, ignoring ASCII case, get the page
+    /// unminified, e.g. for crawlers that misread minified markup. While any are listed,
+    /// minifiable responses vary on `User-Agent`. Defaults to empty.
+    pub bypass_user_agents: Vec<String>,
+    /// Request methods whose responses are minified. Defaults to `GET` and `POST`.
+    pub methods: Vec<Method>,
+    /// Requests whose path starts with one of these prefixes are not minified. Defaults to none.
+    pub skip_path_prefixes: Vec<String>,
+    /// Request paths under which `.html` and `.htm` files are downloads, forwarded as stored,
+    /// e.g. `"/downloads/"`. Defaults to empty.
+    pub download_path_prefixes: Vec<String>,
+    /// `Cache-Control` to set on minified responses under [`static_path_prefixes`], such as
+    /// `public, max-age=31536000, immutable`, unless the handler set one. Defaults to `None`.
+    ///
+    /// [`static_path_prefixes`]: Self::static_path_prefixes
+    pub static_cache_control: Option<HeaderValue>,
+    /// Request paths serving static pages, e.g. `"/docs/"`, whose minified responses get
+    /// [`static_cache_control`](Self::static_cache_control). Defaults to empty.
+    pub static_path_prefixes: Vec<String>,
+    /// Also minify responses with a status of 400 or above, such as error pages and typical
+    /// `Router::fallback` pages. Defaults to `false`.
+    pub minify_error_responses: bool,
+    /// Replace a handler-provided `ETag` with a weak one computed from the minified body.
+    /// A `GET` or `HEAD` whose `If-None-Match` matches it then gets an empty
+    /// `304 Not Modified`. Defaults to `false`.
+    pub recompute_etag: bool,
+    /// Add a `Link: <url>; rel=preload` header for each of the first this many stylesheets
+    /// and scripts a minified page loads, so the browser can fetch them before it has parsed
+    /// the page. Defaults to `0` (none).
+    pub preload_links: usize,
+    /// Minify standalone `text/css` responses. Defaults to `false`.
+    pub minify_css_responses: bool,
+    /// Minify standalone `application/javascript` and `text/javascript` responses.
+    /// Defaults to `false`.
+    pub minify_js_responses: bool,
+    /// Strip comments and indentation from `application/xml`, `text/xml` and
+    /// `image/svg+xml` responses. Defaults to `false`.
+    pub minify_xml: bool,
+    /// Strip insignificant whitespace from `application/json` responses. Defaults to `false`.
+    #[cfg(feature = "json")]
+    pub minify_json: bool,
+    /// JSON pointers, such as `/template/html`, to string fields of `application/json`
+    /// responses that hold HTML to minify. Pointers that don't lead to a string are skipped,
+    /// and invalid JSON is forwarded unchanged. The document is written back compactly, with
+    /// its keys in their original order. Defaults to empty.
+    #[cfg(feature = "json")]
+    pub json_html_pointers: Vec<String>,
+    /// Minify the parts of `multipart/*` responses whose type is one of
+    /// [`content_types`](Self::content_types), leaving the other parts and the boundaries
+    /// byte for byte. Defaults to `false`.
+    #[cfg(feature = "multipart")]
+    pub minify_multipart: bool,
+    /// Compress minified bodies with brotli or gzip when the request's `Accept-Encoding`
+    /// allows it. Defaults to `true`.
+    #[cfg(feature = "compress")]
+    pub compress: bool,
+    /// Cache reusing earlier results for identical bodies. Defaults to `None`.
+    #[cfg(feature = "cache")]
+    pub cache: Option<Arc<MinifyCache>>,
+    /// Replaces `minify_html::minify` for HTML documents, e.g. to use another engine or a
+    /// stub in tests. Defaults to `None`.
+    pub minify_fn: Option<MinifyFn>,
+    /// How long minifying a body may take before the original is sent instead. The minifier
+    /// can't be interrupted, so it keeps running in the background; this only bounds the
+    /// response's latency. Defaults to `None` (no limit).
+    pub timeout: Option<Duration>,
+    /// Bounds how many bodies are minified at once, so a traffic spike can't take over the
+    /// blocking thread pool. A body arriving while every permit is taken is sent unminified.
+    /// Clones of the config share the semaphore. Defaults to `None` (no limit).
+    #[cfg(feature = "axum")]
+    pub max_concurrent: Option<Arc<Semaphore>>,
+    /// Bounds the time spent minifying per window, so a burst of large pages can't take over
+    /// the CPU. Once the budget is spent, bodies are sent unminified until it refills. Clones
+    /// of the config share the budget. Defaults to `None` (no limit).
+    #[cfg(feature = "axum")]
+    pub cpu_budget: Option<Arc<CpuBudget>>,
+    /// Lifetime totals to add every response to. Defaults to `None`.
+    pub stats_collector: Option<Arc<MinifyStatsCollector>>,
+    /// Called whenever a body the middleware would have minified is sent as it was because
+    /// something went wrong, e.g. to feed alerting. Only this top-level callback is called, also
+    /// for bodies minified with [`per_content_type`](Self::per_content_type) settings. Defaults
+    /// to `None`.
+    pub on_skip: Option<OnSkipFn>,
+    /// Runs on HTML bodies just before they are minified, e.g. to inject an analytics
+    /// snippet without buffering the body a second time. Bodies that are forwarded
+    /// unminified skip it. Defaults to `None`.
+    pub pre_process: Option<PreProcessFn>,
+    /// Runs on every minified body before it is sent, e.g. to append a build stamp. Bodies
+    /// that are forwarded unminified skip it. Defaults to `None`.
+    pub post_process: Option<PostProcessFn>,
+    /// Stores the minified CSS of large `<style>` elements and returns the URL to serve it
+    /// from, so the element can be replaced with a cacheable `<link rel=stylesheet>`; return
+    /// `None` to keep a style inline. Storing and serving the CSS is up to the callback, which
+    /// sees the same CSS for every page sharing the stylesheet. Styles inside `<svg>`,
+    /// `<template>` and `<noscript>`, and styles with attributes other than `type` and
+    /// `media`, are never moved, nor is anything with [`amp_mode`](Self::amp_mode). Defaults
+    /// to `None`.
+    pub externalize_styles: Option<ExternalizeStylesFn>,
+    /// Bytes of minified CSS from which [`externalize_styles`](Self::externalize_styles) moves
+    /// a `<style>` element out. Defaults to 4096.
+    pub externalize_styles_min_bytes: usize,
+}
+
+impl Default for MinifierConfig {
+    fn default() -> Self {
+        Self {
+            mode: MinifyMode::Buffered,
+            enabled: None,
+            shutdown: None,
+            dry_run: false,
+            sample_rate: 1.0,
+            verify_rate: 0.0,
+            debug_headers: false,
+            minify_css: true,
+            minify_js: true,
+            keep_comments: false,
+            preserve_comment_prefixes: Vec::new(),
+            keep_important_comments: false,
+            preserve_sensitive_whitespace: false,
+            preserve_whitespace_tags: Vec::new(),
+            honor_no_minify_markers: false,
+            preserve_nonces: false,
+            preserve_data_uris: false,
+            collapse_boolean_attributes: true,
+            remove_bangs: true,
+            remove_processing_instructions: true,
+            allow_removing_spaces_between_attributes: true,
+            remove_optional_closing_tags: true,
+            remove_attribute_quotes: true,
+            fragment_safe: false,
+            amp_mode: false,
+            preserve_trailing_newline: false,
+            minify_html_cfg: None,
+            collapse_class_whitespace: false,
+            minify_inline_styles: false,
+            remove_redundant_type_attrs: false,
+            minify_inline_event_handlers: false,
+            minify_noscript: true,
+            min_size_bytes: 0,
+            skip_if: None,
+            skip_minified_below: None,
+            min_savings_ratio: 1.0,
+            content_types: vec!["text/html".to_owned()],
+            minify_html_suffix_types: false,
+            sniff_html: false,
+            default_to_html_when_empty: false,
+            per_content_type: HashMap::new(),
+            max_body_bytes: None,
+            reject_too_large: false,
+            full_minify_max_bytes: None,
+            fragment_header: None,
+            fragment_content_types: Vec::new(),
+            fragment_max_bytes: 4096,
+            strict_content_length: false,
+            defer_content_length: false,
+            read_error_status: None,
+            read_error_message: None,
+            opt_out_header: Some(HeaderName::from_static("x-minify")),
+            opt_out_query: Some("minify".to_owned()),
+            bypass_user_agents: Vec::new(),
+            methods: vec![Method::GET, Method::POST],
+            skip_path_prefixes: Vec::new(),
+            download_path_prefixes: Vec::new(),
+            static_cache_control: None,
+            static_path_prefixes: Vec::new(),
+            minify_error_responses: false,
+            recompute_etag: false,
+            preload_links: 0,
+            minify_css_responses: false,
+            minify_js_responses: false,
+            minify_xml: false,
+            #[cfg(feature = "json")]
+            minify_json: false,
+            #[cfg(feature = "json")]
+            json_html_pointers: Vec::new(),
+            #[cfg(feature = "multipart")]
+            minify_multipart: false,
+            #[cfg(feature = "compress")]
+            compress: true,
+            #[cfg(feature = "cache")]
+            cache: None,
+            minify_fn: None,
+            timeout: None,
+            #[cfg(feature = "axum")]
+            max_concurrent: None,
+            #[cfg(feature = "axum")]
+            cpu_budget: None,
+            stats_collector: None,
+            on_skip: None,
+            pre_process: None,
+            post_process: None,
+            externalize_styles: None,
+            externalize_styles_min_bytes: 4096,
+        }
+    }
+}
+
+impl fmt::Debug for MinifierConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("MinifierConfig");
+        debug.field("mode", &self.mode);
+        debug.field("enabled", &self.enabled);
+        debug.field("shutdown", &self.shutdown);
+        debug.field("dry_run", &self.dry_run);
+        debug.field("sample_rate", &self.sample_rate);
+        debug.field("verify_rate", &self.verify_rate);
+        debug.field("debug_headers", &self.debug_headers);
+        debug.field("minify_css", &self.minify_css);
+        debug.field("minify_js", &self.minify_js);
+        debug.field("keep_comments", &self.keep_comments);
+        debug.field("preserve_comment_prefixes", &self.preserve_comment_prefixes);
+        debug.field("keep_important_comments", &self.keep_important_comments);
+        debug.field("preserve_sensitive_whitespace", &self.preserve_sensitive_whitespace);
+        debug.field("preserve_whitespace_tags", &self.preserve_whitespace_tags);
+        debug.field("honor_no_minify_markers", &self.honor_no_minify_markers);
+        debug.field("preserve_nonces", &self.preserve_nonces);
+        debug.field("preserve_data_uris", &self.preserve_data_uris);
+        debug.field("collapse_boolean_attributes", &self.collapse_boolean_attributes);
+        debug.field("remove_bangs", &self.remove_bangs);
+        debug.field("remove_processing_instructions", &self.remove_processing_instructions);
+        debug.field("allow_removing_spaces_between_attributes", &self.allow_removing_spaces_between_attributes);
+        debug.field("remove_optional_closing_tags", &self.remove_optional_closing_tags);
+        debug.field("remove_attribute_quotes", &self.remove_attribute_quotes);
+        debug.field("fragment_safe", &self.fragment_safe);
+        debug.field("amp_mode", &self.amp_mode);
+        debug.field("preserve_trailing_newline", &self.preserve_trailing_newline);
+        debug.field("minify_html_cfg", &self.minify_html_cfg.as_ref().map(|_| ".."));
+        debug.field("collapse_class_whitespace", &self.collapse_class_whitespace);
+        debug.field("minify_inline_styles", &self.minify_inline_styles);
+        debug.field("remove_redundant_type_attrs", &self.remove_redundant_type_attrs);
+        debug.field("minify_inline_event_handlers", &self.minify_inline_event_handlers);
+        debug.field("minify_noscript", &self.minify_noscript);
+        debug.field("min_size_bytes", &self.min_size_bytes);
+        debug.field("skip_if", &self.skip_if.as_ref().map(|_| ".."));
+        debug.field("skip_minified_below", &self.skip_minified_below);
+        debug.field("min_savings_ratio", &self.min_savings_ratio);
+        debug.field("content_types", &self.content_types);
+        debug.field("minify_html_suffix_types", &self.minify_html_suffix_types);
+        debug.field("sniff_html", &self.sniff_html);
+        debug.field("default_to_html_when_empty", &self.default_to_html_when_empty);
+        debug.field("per_content_type", &self.per_content_type);
+        debug.field("max_body_bytes", &self.max_body_bytes);
+        debug.field("reject_too_large", &self.reject_too_large);
+        debug.field("full_minify_max_bytes", &self.full_minify_max_bytes);
+        debug.field("fragment_header", &self.fragment_header);
+        debug.field("fragment_content_types", &self.fragment_content_types);
+        debug.field("fragment_max_bytes", &self.fragment_max_bytes);
+        debug.field("strict_content_length", &self.strict_content_length);
+        debug.field("defer_content_length", &self.defer_content_length);
+        debug.field("read_error_status", &self.read_error_status);
+        debug.field("read_error_message", &self.read_error_message);
+        debug.field("opt_out_header", &self.opt_out_header);
+        debug.field("opt_out_query", &self.opt_out_query);
+        debug.field("bypass_user_agents", &self.bypass_user_agents);
+        debug.field("methods", &self.methods);
+        debug.field("skip_path_prefixes", &self.skip_path_prefixes);
+        debug.field("download_path_prefixes", &self.download_path_prefixes);
+        debug.field("static_cache_control", &self.static_cache_control);
+        debug.field("static_path_prefixes", &self.static_path_prefixes);
+        debug.field("minify_error_responses", &self.minify_error_responses);
+        debug.field("recompute_etag", &self.recompute_etag);
+        debug.field("preload_links", &self.preload_links);
+        debug.field("minify_css_responses", &self.minify_css_responses);
+        debug.field("minify_js_responses", &self.minify_js_responses);
+        debug.field("minify_xml", &self.minify_xml);
+        #[cfg(feature = "json")]
+        debug.field("minify_json", &self.minify_json);
+        #[cfg(feature = "json")]
+        debug.field("json_html_pointers", &self.json_html_pointers);
+        #[cfg(feature = "multipart")]
+        debug.field("minify_multipart", &self.minify_multipart);
+        #[cfg(feature = "compress")]
+        debug.field("compress", &self.compress);
+        #[cfg(feature = "cache")]
+        debug.field("cache", &self.cache);
+        debug.field("minify_fn", &self.minify_fn.as_ref().map(|_| ".."));
+        debug.field("timeout", &self.timeout);
+        #[cfg(feature = "axum")]
+        debug.field("max_concurrent", &self.max_concurrent);
+        #[cfg(feature = "axum")]
+        debug.field("cpu_budget", &self.cpu_budget);
+        debug.field("stats_collector", &self.stats_collector);
+        debug.field("on_skip", &self.on_skip.as_ref().map(|_| ".."));
+        debug.field("pre_process", &self.pre_process.as_ref().map(|_| ".."));
+        debug.field("post_process", &self.post_process.as_ref().map(|_| ".."));
+        debug.field("externalize_styles", &self.externalize_styles.as_ref().map(|_| ".."));
+        debug.field("externalize_styles_min_bytes", &self.externalize_styles_min_bytes);
+        debug.finish()
+    }
+}
+
+impl MinifierConfig {
+    /// Preset that mostly just removes whitespace: comments, bangs, processing instructions
+    /// and embedded CSS and JavaScript are kept as written, as is the content of `<pre>`,
+    /// `<textarea>` and `<code>` and any `nonce` attribute, and fragments keep their closing
+    /// tags. The safest starting point for unusual markup.
+    pub fn conservative() -> Self {
+        Self {
+            minify_css: false,
+            minify_js: false,
+            keep_comments: true,
+            preserve_sensitive_whitespace: true,
+            remove_bangs: false,
+            remove_processing_instructions: false,
+            allow_removing_spaces_between_attributes: false,
+            fragment_safe: true,
+            preserve_nonces: true,
+            preserve_data_uris: true,
+            ..Self::default()
+        }
+    }
+
+    /// Preset that turns on every optimization: on top of the defaults, `class` and `style`
+    /// attributes are compacted and standalone CSS, JavaScript and XML responses are minified
+    /// too.
+    pub fn aggressive() -> Self {
+        Self {
+            collapse_class_whitespace: true,
+            minify_inline_styles: true,
+            remove_redundant_type_attrs: true,
+            minify_css_responses: true,
+            minify_js_responses: true,
+            minify_xml: true,
+            #[cfg(feature = "json")]
+            minify_json: true,
+            #[cfg(feature = "multipart")]
+            minify_multipart: true,
+            ..Self::default()
+        }
+    }
+
+    /// Starts a [`MinifierConfigBuilder`] from the default settings.
+    pub fn builder() -> MinifierConfigBuilder {
+        MinifierConfigBuilder::default()
+    }
+
+    /// The default settings, adjusted by environment variables read once, e.g. at startup:
+    ///
+    /// - `MINIFY_HTML`: `on` or `off` (also `true`/`false`, `1`/`0`, `yes`/`no`) switches
+    ///   minification on or off.
+    /// - `MINIFY_HTML_MIN_SIZE`: sets [`min_size_bytes`](Self::min_size_bytes).
+    /// - `MINIFY_HTML_KEEP_COMMENTS`: sets [`keep_comments`](Self::keep_comments), with the
+    ///   same values as `MINIFY_HTML`.
+    ///
+    /// Values are case-insensitive. Unset variables keep the default, and so do unparseable
+    /// ones, which are logged as warnings with the `tracing` feature.
+    ///
+    /// ```no_run
+    /// use axum_mini::MinifierConfig;
+    ///
+    /// // SAFETY: no other thread reads the environment in this example
+    /// unsafe {
+    ///     std::env::set_var("MINIFY_HTML", "OFF");
+    ///     std::env::set_var("MINIFY_HTML_MIN_SIZE", "1024");
+    ///     std::env::set_var("MINIFY_HTML_KEEP_COMMENTS", "not a flag");
+    /// }
+    /// let config = MinifierConfig::from_env();
+    /// assert!(!config.is_enabled());
+    /// assert_eq!(config.min_size_bytes, 1024);
+    /// assert!(!config.keep_comments);
+    ///
+    /// unsafe {
+    ///     std::env::set_var("MINIFY_HTML", " yes ");
+    ///     std::env::remove_var("MINIFY_HTML_MIN_SIZE");
+    ///     std::env::set_var("MINIFY_HTML_KEEP_COMMENTS", "True");
+    /// }
+    /// let config = MinifierConfig::from_env();
+    /// assert!(config.is_enabled());
+    /// assert_eq!(config.min_size_bytes, 0);
+    /// assert!(config.keep_comments);
+    /// ```
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(enabled) = env_var("MINIFY_HTML", parse_switch) {
+            config.enabled = Some(Arc::new(AtomicBool::new(enabled)));
+        }
+        if let Some(min_size_bytes) = env_var("MINIFY_HTML_MIN_SIZE", |value| value.parse().ok()) {
+            config.min_size_bytes = min_size_bytes;
+        }
+        if let Some(keep_comments) = env_var("MINIFY_HTML_KEEP_COMMENTS", parse_switch) {
+            config.keep_comments = keep_comments;
+        }
+        config
+    }
+
+    /// Checks for settings that contradict each other or leave nothing to minify, so that a
+    /// misconfiguration surfaces at startup rather than as a minifier that silently does
+    /// nothing.
+    ///
+    /// ```
+    /// use axum_mini::{ConfigError, ContentTypeClass, MinifierConfig};
+    ///
+    /// assert_eq!(MinifierConfig::default().validate(), Ok(()));
+    ///
+    /// let config = MinifierConfig::builder().min_size_bytes(4096).max_body_bytes(1024).build();
+    /// let err = config.validate().unwrap_err();
+    /// assert_eq!(err, ConfigError::MinSizeAboveMaxBody { min_size_bytes: 4096, max_body_bytes: 1024 });
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "min_size_bytes (4096) is larger than max_body_bytes (1024), so nothing would be minified",
+    /// );
+    ///
+    /// let config = MinifierConfig::builder().content_types(Vec::<String>::new()).build();
+    /// assert_eq!(config.validate(), Err(ConfigError::NoContentTypes));
+    ///
+    /// let config = MinifierConfig::builder().sample_rate(1.5).build();
+    /// assert_eq!(config.validate().unwrap_err().to_string(), "sample_rate (1.5) must be between 0.0 and 1.0");
+    ///
+    /// let config = MinifierConfig::builder().reject_too_large(true).build();
+    /// assert_eq!(config.validate(), Err(ConfigError::RejectWithoutLimit));
+    ///
+    /// let css = MinifierConfig::builder().verify_rate(-1.0).build();
+    /// let config = MinifierConfig::builder().content_type_config(ContentTypeClass::Css, css).build();
+    /// assert_eq!(
+    ///     config.validate().unwrap_err().to_string(),
+    ///     "per_content_type entry for Css: verify_rate (-1) must be between 0.0 and 1.0",
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.validate_document_settings(self.max_body_bytes)?;
+        if !self.any_content_type() {
+            return Err(ConfigError::NoContentTypes);
+        }
+        if self.methods.is_empty() {
+            return Err(ConfigError::NoMethods);
+        }
+        check_rate("sample_rate", self.sample_rate)?;
+        if self.reject_too_large && self.max_body_bytes.is_none() {
+            return Err(ConfigError::RejectWithoutLimit);
+        }
+        #[cfg(feature = "axum")]
+        if self.max_concurrent.as_ref().is_some_and(|semaphore| semaphore.available_permits() == 0) {
+            return Err(ConfigError::NoConcurrency);
+        }
+        for (&class, entry) in &self.per_content_type {
+            entry
+                .validate_document_settings(self.max_body_bytes)
+                .map_err(|error| ConfigError::PerContentType { class, error: Box::new(error) })?;
+        }
+        Ok(())
+    }
+
+    /// Checks the settings that still apply once a document has been classified.
+    fn validate_document_settings(&self, max_body_bytes: Option<usize>) -> Result<(), ConfigError> {
+        if let Some(max_body_bytes) = max_body_bytes.filter(|&max| self.min_size_bytes > max) {
+            return Err(ConfigError::MinSizeAboveMaxBody { min_size_bytes: self.min_size_bytes, max_body_bytes });
+        }
+        check_rate("verify_rate", self.verify_rate)?;
+        if let Some(threshold) = self.skip_minified_below {
+            check_rate("skip_minified_below", threshold)?;
+        }
+        check_rate("min_savings_ratio", self.min_savings_ratio)?;
+        Ok(())
+    }
+
+    /// Whether any kind of body would be minified.
+    fn any_content_type(&self) -> bool {
+        #[cfg(feature = "json")]
+        let json = self.minify_json || !self.json_html_pointers.is_empty();
+        #[cfg(not(feature = "json"))]
+        let json = false;
+        #[cfg(feature = "multipart")]
+        let multipart = self.minify_multipart;
+        #[cfg(not(feature = "multipart"))]
+        let multipart = false;
+        !self.content_types.is_empty()
+            || self.minify_html_suffix_types
+            || self.sniff_html
+            || self.default_to_html_when_empty
+            || self.minify_css_responses
+            || self.minify_js_responses
+            || self.minify_xml
+            || json
+            || multipart
+    }
+
+    /// Whether minification is currently switched on.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.as_ref().is_none_or(|enabled| enabled.load(Ordering::Relaxed))
+    }
+
+    /// Whether the [`shutdown`](Self::shutdown) flag has been set.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.as_ref().is_some_and(|shutdown| shutdown.load(Ordering::Relaxed))
+    }
+
+    /// Builds the `minify_html` configuration for these settings.
+    pub(crate) fn to_cfg(&self) -> Cfg {
+        engine::cfg(self)
+    }
+
+    /// Hash of the settings that shape minified output, so that a shared cache keeps apart
+    /// the results of differently configured minifiers, including per-request overrides.
+    /// Callbacks are told apart by identity.
+    #[cfg(feature = "cache")]
+    pub(crate) fn output_fingerprint(&self) -> u64 {
+        let mut state = std::collections::hash_map::DefaultHasher::new();
+        engine::hash_cfg(&self.to_cfg(), &mut state);
+        [
+            self.keep_important_comments,
+            self.preserve_sensitive_whitespace,
+            self.honor_no_minify_markers,
+            self.preserve_nonces,
+            self.preserve_data_uris,
+            self.collapse_boolean_attributes,
+            self.remove_attribute_quotes,
+            self.fragment_safe,
+            self.amp_mode,
+            self.preserve_trailing_newline,
+            self.collapse_class_whitespace,
+            self.minify_inline_styles,
+            self.remove_redundant_type_attrs,
+            self.minify_inline_event_handlers,
+            self.minify_noscript,
+        ]
+        .hash(&mut state);
+        self.preserve_comment_prefixes.hash(&mut state);
+        self.preserve_whitespace_tags.hash(&mut state);
+        self.full_minify_max_bytes.hash(&mut state);
+        // Multipart bodies are minified part by part, picked by content type
+        self.content_types.hash(&mut state);
+        #[cfg(feature = "json")]
+        (self.minify_json, &self.json_html_pointers).hash(&mut state);
+        self.minify_fn.as_ref().map(Arc::as_ptr).map(<*const _>::cast::<()>).hash(&mut state);
+        self.externalize_styles.as_ref().map(Arc::as_ptr).map(<*const _>::cast::<()>).hash(&mut state);
+        self.externalize_styles_min_bytes.hash(&mut state);
+        state.finish()
+    }
+
+    /// Minifies an HTML document with the configured engine.
+    pub(crate) fn run_minifier(&self, input: &[u8], cfg: &Cfg) -> Vec<u8> {
+        match &self.minify_fn {
+            Some(minify_fn) => minify_fn(input, cfg),
+            None => engine::minify(input, cfg),
+        }
+    }
+}
+
+/// Reads and parses the environment variable `name`, if it is set.
+fn env_var<T>(name: &str, parse: impl Fn(&str) -> Option<T>) -> Option<T> {
+    let value = std::env::var(name).ok()?;
+    let parsed = parse(value.trim());
+    #[cfg(feature = "tracing")]
+    if parsed.is_none() {
+        tracing::warn!(name, value, "ignoring unparseable environment variable");
+    }
+    parsed
+}
+
+/// Fails unless `value` is a fraction from `0.0` to `1.0`.
+fn check_rate(name: &'static str, value: f64) -> Result<(), ConfigError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(ConfigError::RateOutOfRange { name, value })
+    }
+}
+
+/// Parses an on/off switch such as `on`, `false` or `1`.
+fn parse_switch(value: &str) -> Option<bool> {
+    let is = |options: [&str; 4]| options.iter().any(|option| value.eq_ignore_ascii_case(option));
+    if is(["on", "true", "1", "yes"]) {
+        Some(true)
+    } else if is(["off", "false", "0", "no"]) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Chainable builder for [`MinifierConfig`], starting from the defaults.
+///
+/// ```
+/// use axum::Router;
+/// use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+///
+/// let config = MinifierConfig::builder()
+///     .minify_js(false)
+///     .min_size(256)
+///     .content_types(["text/html", "application/xhtml+xml"])
+///     .build();
+///
+/// let app: Router = Router::new()
+///     .route("/", axum::routing::get(|| async { "<h1>Hello World!</h1>" }))
+///     .layer(HtmlMinifierLayer::with_config(config));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MinifierConfigBuilder {
+    config: MinifierConfig,
+}
+
+impl MinifierConfigBuilder {
+    /// Sets [`MinifierConfig::mode`].
+    pub fn mode(mut self, mode: MinifyMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    /// Sets [`MinifierConfig::enabled`].
+    pub fn enabled(mut self, enabled: Arc<AtomicBool>) -> Self {
+        self.config.enabled = Some(enabled);
+        self
+    }
+
+    /// Sets [`MinifierConfig::shutdown`].
+    ///
+    /// ```
+    /// use std::sync::{
+    ///     atomic::{AtomicBool, Ordering},
+    ///     mpsc, Arc, Mutex,
+    /// };
+    ///
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// fn page() -> Response<Body> {
+    ///     Response::builder()
+    ///         .header(header::CONTENT_TYPE, "text/html")
+    ///         .body(Body::from("<p>  Hello  </p>"))
+    ///         .unwrap()
+    /// }
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let (started, mut has_started) = tokio::sync::mpsc::unbounded_channel();
+    /// let (release, released) = mpsc::channel::<()>();
+    /// let released = Mutex::new(released);
+    /// let shutdown = Arc::new(AtomicBool::new(false));
+    /// let config = MinifierConfig::builder()
+    ///     .shutdown(shutdown.clone())
+    ///     // Runs until released
+    ///     .minify_fn(move |_html, _cfg| {
+    ///         started.send(()).unwrap();
+    ///         released.lock().unwrap().recv().unwrap();
+    ///         b"<p>Slow".to_vec()
+    ///     })
+    ///     .build();
+    ///
+    /// let in_flight = tokio::spawn({
+    ///     let config = config.clone();
+    ///     async move { minify_response(page(), &config).await }
+    /// });
+    /// has_started.recv().await.unwrap();
+    /// shutdown.store(true, Ordering::Relaxed);
+    ///
+    /// // New responses no longer wait for the minifier
+    /// let response = minify_response(page(), &config).await;
+    /// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<p>  Hello  </p>");
+    ///
+    /// // The one already being minified still is
+    /// release.send(()).unwrap();
+    /// let response = in_flight.await.unwrap();
+    /// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<p>Slow");
+    /// # });
+    /// ```
+    pub fn shutdown(mut self, shutdown: Arc<AtomicBool>) -> Self {
+        self.config.shutdown = Some(shutdown);
+        self
+    }
+
+    /// Sets [`MinifierConfig::dry_run`].
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.config.dry_run = dry_run;
+        self
+    }
+
+    /// Sets [`MinifierConfig::sample_rate`].
+    pub fn sample_rate(mut self, rate: f64) -> Self {
+        self.config.sample_rate = rate;
+        self
+    }
+
+    /// Sets [`MinifierConfig::verify_rate`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig, MinifyStats};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// // A broken minifier that loses the page's content
+    /// let config = MinifierConfig::builder()
+    ///     .minify_fn(|_, _| b"<p></p>".to_vec())
+    ///     .verify_rate(1.0)
+    ///     .build();
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .body(Body::from("<p>  Hello,   world!  </p>"))
+    ///     .unwrap();
+    ///
+    /// let response = minify_response(response, &config).await;
+    /// assert!(!response.extensions().get::<MinifyStats>().unwrap().minified);
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>  Hello,   world!  </p>");
+    /// # });
+    /// ```
+    pub fn verify_rate(mut self, rate: f64) -> Self {
+        self.config.verify_rate = rate;
+        self
+    }
+
+    /// Sets [`MinifierConfig::debug_headers`].
+    pub fn debug_headers(mut self, debug_headers: bool) -> Self {
+        self.config.debug_headers = debug_headers;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_css`].
+    pub fn minify_css(mut self, minify_css: bool) -> Self {
+        self.config.minify_css = minify_css;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_js`].
+    pub fn minify_js(mut self, minify_js: bool) -> Self {
+        self.config.minify_js = minify_js;
+        self
+    }
+
+    /// Sets [`MinifierConfig::keep_comments`].
+    pub fn keep_comments(mut self, keep_comments: bool) -> Self {
+        self.config.keep_comments = keep_comments;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_comment_prefixes`].
+    pub fn preserve_comment_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.preserve_comment_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::keep_important_comments`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = b"<!-- build 42 --><style>/*! theme.css | MIT */ p { color : red }</style>\
+    ///     <script>/*! app.js | MIT */ let  answer = 42 ;</script>";
+    /// let config = MinifierConfig::builder().keep_important_comments(true).build();
+    /// assert_eq!(
+    ///     minify_html_bytes(html, &config),
+    ///     b"<style>/*! theme.css | MIT */\np{color:red}</style><script>/*! app.js | MIT */\nlet answer=42</script>",
+    /// );
+    /// ```
+    pub fn keep_important_comments(mut self, keep: bool) -> Self {
+        self.config.keep_important_comments = keep;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_sensitive_whitespace`].
+    pub fn preserve_sensitive_whitespace(mut self, preserve: bool) -> Self {
+        self.config.preserve_sensitive_whitespace = preserve;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_whitespace_tags`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = "<div>\n  <x-verbatim  lang=\"sh\">\n  $ cargo run\n    Running\n</x-verbatim>\n</div>";
+    /// let config = MinifierConfig::builder().preserve_whitespace_tags(["x-verbatim"]).build();
+    /// assert_eq!(
+    ///     minify_html_bytes(html.as_bytes(), &config),
+    ///     "<div><x-verbatim lang=sh>\n  $ cargo run\n    Running\n</x-verbatim></div>".as_bytes(),
+    /// );
+    /// ```
+    pub fn preserve_whitespace_tags<I, T>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.preserve_whitespace_tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::honor_no_minify_markers`].
+    pub fn honor_no_minify_markers(mut self, honor: bool) -> Self {
+        self.config.honor_no_minify_markers = honor;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_nonces`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = br#"<script nonce="r4nd0m+Base64/==" src=app.js></script><style nonce='abc123'>p { color: red }</style><script nonce="">go()</script>"#;
+    /// let minified = minify_html_bytes(html, &MinifierConfig::default());
+    /// assert_eq!(minified, br#"<script nonce="r4nd0m+Base64/=="src=app.js></script><style nonce=abc123>p{color:red}</style><script>go()</script>"#);
+    ///
+    /// let config = MinifierConfig::builder().preserve_nonces(true).build();
+    /// assert_eq!(
+    ///     minify_html_bytes(html, &config),
+    ///     br#"<script src=app.js nonce="r4nd0m+Base64/=="></script><style nonce='abc123'>p{color:red}</style><script nonce="">go()</script>"#,
+    /// );
+    /// ```
+    pub fn preserve_nonces(mut self, preserve: bool) -> Self {
+        self.config.preserve_nonces = preserve;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_data_uris`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let src = r#"src="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==""#;
+    /// let style = r#"<style>p { background: url( "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg'/>" ) }</style>"#;
+    /// let html = format!("{style}\n<p>\n  <img {src} alt=\"\">\n</p>");
+    /// let config = MinifierConfig::builder().minify_css(true).preserve_data_uris(true).build();
+    /// let minified = String::from_utf8(minify_html_bytes(html.as_bytes(), &config)).unwrap();
+    /// assert!(minified.contains(src));
+    /// assert!(minified.contains(r#"url( "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg'/>" )"#));
+    /// assert!(minified.len() < html.len());
+    /// ```
+    pub fn preserve_data_uris(mut self, preserve: bool) -> Self {
+        self.config.preserve_data_uris = preserve;
+        self
+    }
+
+    /// Sets [`MinifierConfig::collapse_boolean_attributes`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = br#"<input type="checkbox" checked="checked"><button disabled>Go</button>"#;
+    /// let minified = minify_html_bytes(html, &MinifierConfig::default());
+    /// assert_eq!(minified, b"<input checked type=checkbox><button disabled>Go</button>");
+    ///
+    /// let config = MinifierConfig::builder().collapse_boolean_attributes(false).build();
+    /// assert_eq!(minify_html_bytes(html, &config), br#"<input type=checkbox checked="checked"><button disabled>Go</button>"#);
+    /// ```
+    pub fn collapse_boolean_attributes(mut self, collapse: bool) -> Self {
+        self.config.collapse_boolean_attributes = collapse;
+        self
+    }
+
+    /// Sets [`MinifierConfig::remove_bangs`].
+    pub fn remove_bangs(mut self, remove_bangs: bool) -> Self {
+        self.config.remove_bangs = remove_bangs;
+        self
+    }
+
+    /// Sets [`MinifierConfig::remove_processing_instructions`].
+    pub fn remove_processing_instructions(mut self, remove: bool) -> Self {
+        self.config.remove_processing_instructions = remove;
+        self
+    }
+
+    /// Sets [`MinifierConfig::allow_removing_spaces_between_attributes`].
+    pub fn allow_removing_spaces_between_attributes(mut self, allow: bool) -> Self {
+        self.config.allow_removing_spaces_between_attributes = allow;
+        self
+    }
+
+    /// Sets [`MinifierConfig::remove_optional_closing_tags`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = b"<ul>\n  <li>One</li>\n  <li>Two</li>\n</ul>\n<p>Done</p>";
+    /// assert_eq!(minify_html_bytes(html, &MinifierConfig::default()), b"<ul><li>One<li>Two</ul><p>Done");
+    ///
+    /// let config = MinifierConfig::builder().remove_optional_closing_tags(false).build();
+    /// assert_eq!(minify_html_bytes(html, &config), b"<ul><li>One</li><li>Two</li></ul><p>Done</p>");
+    /// ```
+    pub fn remove_optional_closing_tags(mut self, remove: bool) -> Self {
+        self.config.remove_optional_closing_tags = remove;
+        self
+    }
+
+    /// Sets [`MinifierConfig::remove_attribute_quotes`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = br#"<a class="nav" href="/docs" title='Read "this"'>Docs</a>"#;
+    /// assert_eq!(
+    ///     minify_html_bytes(html, &MinifierConfig::default()),
+    ///     br#"<a title='Read "this"'class=nav href=/docs>Docs</a>"#
+    /// );
+    ///
+    /// let config = MinifierConfig::builder().remove_attribute_quotes(false).build();
+    /// assert_eq!(
+    ///     minify_html_bytes(html, &config),
+    ///     br#"<a title='Read "this"'class="nav" href="/docs">Docs</a>"#
+    /// );
+    /// ```
+    pub fn remove_attribute_quotes(mut self, remove: bool) -> Self {
+        self.config.remove_attribute_quotes = remove;
+        self
+    }
+
+    /// Sets [`MinifierConfig::fragment_safe`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let fragment = b"<div id=row>\n  <p>One</p>\n  <p>Two</p>\n</div>";
+    /// let config = MinifierConfig::builder().fragment_safe(true).build();
+    /// assert_eq!(minify_html_bytes(fragment, &config), b"<div id=row><p>One</p><p>Two</p></div>");
+    /// ```
+    pub fn fragment_safe(mut self, fragment_safe: bool) -> Self {
+        self.config.fragment_safe = fragment_safe;
+        self
+    }
+
+    /// Sets [`MinifierConfig::amp_mode`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let boilerplate = "<style amp-boilerplate>body{-webkit-animation:-amp-start 8s steps(1,end) 0s 1 normal both;animation:-amp-start 8s steps(1,end) 0s 1 normal both}@keyframes -amp-start{from{visibility:hidden}to{visibility:visible}}</style>";
+    /// let noscript = "<style amp-boilerplate>body{-webkit-animation:none;animation:none}</style>";
+    /// let page = format!(
+    ///     r#"<!doctype html>
+    /// <html ⚡ lang="en">
+    ///   <head>
+    ///     <meta charset="utf-8">
+    ///     <script async src="https://cdn.ampproject.org/v0.js"></script>
+    ///     {boilerplate}<noscript>{noscript}</noscript>
+    ///     <style amp-custom>
+    ///       h1  {{  color : red ; }}
+    ///     </style>
+    ///   </head>
+    ///   <body>
+    ///     <h1>  Hello  </h1>
+    ///     <amp-img src="hero.jpg" width="600" height="400" layout="responsive"></amp-img>
+    ///     <ul><li>One</li><li>Two</li></ul>
+    ///   </body>
+    /// </html>"#
+    /// );
+    ///
+    /// let config = MinifierConfig::builder().amp_mode(true).build();
+    /// let minified = String::from_utf8(minify_html_bytes(page.as_bytes(), &config)).unwrap();
+    /// assert_eq!(
+    ///     minified,
+    ///     format!(
+    ///         "<!doctype html><html lang=en ⚡><head><meta charset=utf-8>\
+    ///          <script async src=https://cdn.ampproject.org/v0.js></script>\
+    ///          {boilerplate}<noscript>{noscript}</noscript><style amp-custom>h1{{color:red}}</style></head><body><h1>Hello</h1>\
+    ///          <amp-img height=400 layout=responsive src=hero.jpg width=600></amp-img>\
+    ///          <ul><li>One</li><li>Two</li></ul></body></html>"
+    ///     )
+    /// );
+    ///
+    /// // The default rewrites the boilerplate, which then fails AMP validation
+    /// let minified = String::from_utf8(minify_html_bytes(page.as_bytes(), &MinifierConfig::default())).unwrap();
+    /// assert!(!minified.contains(boilerplate));
+    /// ```
+    pub fn amp_mode(mut self, amp_mode: bool) -> Self {
+        self.config.amp_mode = amp_mode;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preserve_trailing_newline`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let config = MinifierConfig::builder().preserve_trailing_newline(true).build();
+    /// assert_eq!(minify_html_bytes(b"<p>  Hello  </p>\n\n", &config), b"<p>Hello\n");
+    /// assert_eq!(minify_html_bytes(b"<p>  Hello  </p>", &config), b"<p>Hello");
+    ///
+    /// let config = MinifierConfig::default();
+    /// assert_eq!(minify_html_bytes(b"<p>  Hello  </p>\n", &config), b"<p>Hello");
+    /// assert_eq!(minify_html_bytes(b"<p>  Hello  </p>", &config), b"<p>Hello");
+    /// ```
+    pub fn preserve_trailing_newline(mut self, preserve: bool) -> Self {
+        self.config.preserve_trailing_newline = preserve;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_html_cfg`].
+    ///
+    /// ```
+    /// use axum::{middleware, Router};
+    /// use axum_mini::{html_minifier_with, Cfg, MinifierConfig};
+    ///
+    /// let mut cfg = Cfg::new();
+    /// cfg.minify_js = false;
+    /// let config = MinifierConfig::builder().minify_html_cfg(cfg).build();
+    ///
+    /// let app: Router = Router::new()
+    ///     .route("/", axum::routing::get(|| async { "<h1>Hello World!</h1>" }))
+    ///     .layer(middleware::from_fn_with_state(config, html_minifier_with));
+    /// ```
+    pub fn minify_html_cfg(mut self, cfg: Cfg) -> Self {
+        self.config.minify_html_cfg = Some(cfg);
+        self
+    }
+
+    /// Sets [`MinifierConfig::collapse_class_whitespace`].
+    pub fn collapse_class_whitespace(mut self, collapse: bool) -> Self {
+        self.config.collapse_class_whitespace = collapse;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_inline_styles`].
+    pub fn minify_inline_styles(mut self, minify: bool) -> Self {
+        self.config.minify_inline_styles = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::remove_redundant_type_attrs`].
+    pub fn remove_redundant_type_attrs(mut self, remove: bool) -> Self {
+        self.config.remove_redundant_type_attrs = remove;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_inline_event_handlers`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = br#"<button onclick="save( '{{ item.id }}' ) ;">Save</button>"#;
+    /// let config = MinifierConfig::builder().build();
+    /// assert_eq!(minify_html_bytes(html, &config), br#"<button onclick="save( '{{ item.id }}' ) ;">Save</button>"#);
+    ///
+    /// let config = MinifierConfig::builder().minify_inline_event_handlers(true).build();
+    /// assert_eq!(minify_html_bytes(html, &config), br#"<button onclick="save(`{{ item.id }}`)">Save</button>"#);
+    /// ```
+    pub fn minify_inline_event_handlers(mut self, minify: bool) -> Self {
+        self.config.minify_inline_event_handlers = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_noscript`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let html = b"<noscript>\n  <div class=\"notice\">  Please   enable  JavaScript.  </div>\n</noscript><p>Hi</p>";
+    /// let minified = minify_html_bytes(html, &MinifierConfig::default());
+    /// assert_eq!(minified, b"<noscript><div class=notice>Please enable JavaScript.</div></noscript><p>Hi");
+    ///
+    /// let config = MinifierConfig::builder().minify_noscript(false).build();
+    /// assert_eq!(
+    ///     minify_html_bytes(html, &config),
+    ///     b"<noscript>\n  <div class=\"notice\">  Please   enable  JavaScript.  </div>\n</noscript><p>Hi",
+    /// );
+    /// ```
+    pub fn minify_noscript(mut self, minify: bool) -> Self {
+        self.config.minify_noscript = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::min_size_bytes`].
+    pub fn min_size_bytes(mut self, min_size_bytes: usize) -> Self {
+        self.config.min_size_bytes = min_size_bytes;
+        self
+    }
+
+    /// Sets [`MinifierConfig::min_size_bytes`], like [`min_size_bytes`](Self::min_size_bytes).
+    pub fn min_size(self, min_size_bytes: usize) -> Self {
+        self.min_size_bytes(min_size_bytes)
+    }
+
+    /// Sets [`MinifierConfig::skip_if`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder()
+    ///     .skip_if(|parts, _body| parts.headers.get("x-render-mode").is_some_and(|mode| mode == "debug"))
+    ///     .build();
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .header("x-render-mode", "debug")
+    ///     .body(Body::from("<p>  Hello  </p>"))
+    ///     .unwrap();
+    /// let response = minify_response(response, &config).await;
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>  Hello  </p>");
+    /// # });
+    /// ```
+    pub fn skip_if<F>(mut self, skip_if: F) -> Self
+    where
+        F: Fn(&Parts, &[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.config.skip_if = Some(Arc::new(skip_if));
+        self
+    }
+
+    /// Sets [`MinifierConfig::skip_minified_below`].
+    pub fn skip_minified_below(mut self, threshold: f64) -> Self {
+        self.config.skip_minified_below = Some(threshold);
+        self
+    }
+
+    /// Sets [`MinifierConfig::min_savings_ratio`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig, MinifyStats};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder().min_savings_ratio(0.9).build();
+    /// let page = |html: &'static str| {
+    ///     Response::builder().header(header::CONTENT_TYPE, "text/html").body(Body::from(html)).unwrap()
+    /// };
+    ///
+    /// // Dropping `</p>` saves 4 of 44 bytes, under 10%
+    /// let response = minify_response(page("<p>Hello, world! This is already tight.</p>"), &config).await;
+    /// assert!(!response.extensions().get::<MinifyStats>().unwrap().minified);
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>Hello, world! This is already tight.</p>");
+    ///
+    /// let response = minify_response(page("<p>\n    Hello,   world!\n</p>\n"), &config).await;
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>Hello, world!");
+    /// # });
+    /// ```
+    pub fn min_savings_ratio(mut self, ratio: f64) -> Self {
+        self.config.min_savings_ratio = ratio;
+        self
+    }
+
+    /// Sets [`MinifierConfig::content_types`].
+    pub fn content_types<I, T>(mut self, content_types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.content_types = content_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_html_suffix_types`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let minify = |config| async move {
+    ///     let response = Response::builder()
+    ///         .header(header::CONTENT_TYPE, "application/vnd.acme+html; charset=utf-8")
+    ///         .body(Body::from("<p>  Hello  </p>"))
+    ///         .unwrap();
+    ///     let response = minify_response(response, &config).await;
+    ///     response.into_body().collect().await.unwrap().to_bytes()
+    /// };
+    ///
+    /// assert_eq!(minify(MinifierConfig::default()).await, "<p>  Hello  </p>");
+    /// let config = MinifierConfig::builder().minify_html_suffix_types(true).build();
+    /// assert_eq!(minify(config).await, "<p>Hello");
+    /// # });
+    /// ```
+    pub fn minify_html_suffix_types(mut self, minify: bool) -> Self {
+        self.config.minify_html_suffix_types = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::sniff_html`].
+    pub fn sniff_html(mut self, sniff: bool) -> Self {
+        self.config.sniff_html = sniff;
+        self
+    }
+
+    /// Sets [`MinifierConfig::default_to_html_when_empty`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, HeaderValue, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// async fn minify(content_type: &[u8], config: &MinifierConfig) -> String {
+    ///     let response = Response::builder()
+    ///         .header(header::CONTENT_TYPE, HeaderValue::from_bytes(content_type).unwrap())
+    ///         .body(Body::from("<p>  Hello  </p>"))
+    ///         .unwrap();
+    ///     let body = minify_response(response, config).await.into_body().collect().await.unwrap().to_bytes();
+    ///     String::from_utf8(body.to_vec()).unwrap()
+    /// }
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let strict = MinifierConfig::default();
+    /// let lenient = MinifierConfig::builder().default_to_html_when_empty(true).build();
+    ///
+    /// for empty in [b"".as_slice(), b"   ", b"\t"] {
+    ///     assert_eq!(minify(empty, &strict).await, "<p>  Hello  </p>");
+    ///     assert_eq!(minify(empty, &lenient).await, "<p>Hello");
+    /// }
+    /// // Malformed and wildcard types are left alone either way
+    /// for unknown in [b"text/h\xe9ml".as_slice(), b"*/*"] {
+    ///     assert_eq!(minify(unknown, &strict).await, "<p>  Hello  </p>");
+    ///     assert_eq!(minify(unknown, &lenient).await, "<p>  Hello  </p>");
+    /// }
+    /// # });
+    /// ```
+    pub fn default_to_html_when_empty(mut self, default: bool) -> Self {
+        self.config.default_to_html_when_empty = default;
+        self
+    }
+
+    /// Adds an entry to [`MinifierConfig::per_content_type`].
+    pub fn content_type_config(mut self, class: ContentTypeClass, config: MinifierConfig) -> Self {
+        self.config.per_content_type.insert(class, config);
+        self
+    }
+
+    /// Sets [`MinifierConfig::max_body_bytes`].
+    pub fn max_body_bytes(mut self, max_body_bytes: usize) -> Self {
+        self.config.max_body_bytes = Some(max_body_bytes);
+        self
+    }
+
+    /// Sets [`MinifierConfig::reject_too_large`].
+    pub fn reject_too_large(mut self, reject: bool) -> Self {
+        self.config.reject_too_large = reject;
+        self
+    }
+
+    /// Sets [`MinifierConfig::full_minify_max_bytes`].
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let config = MinifierConfig::builder().full_minify_max_bytes(1024).build();
+    /// let row = "<tr>\n    <td>  cell  </td>\n  </tr>\n";
+    /// let page = format!("<table>\n{}</table>\n<script>let  x = 1 ;</script>", row.repeat(100));
+    ///
+    /// let minified = minify_html_bytes(page.as_bytes(), &config);
+    /// let expected = format!("<table>\n{}</table>\n<script>let  x = 1 ;</script>", "<tr>\n<td> cell </td>\n</tr>\n".repeat(100));
+    /// assert_eq!(minified, expected.as_bytes());
+    /// assert!(minified.len() < page.len());
+    ///
+    /// // Small documents are still fully minified
+    /// assert_eq!(minify_html_bytes(b"<script>let  x = 1 ;</script>", &config), b"<script>let x=1</script>");
+    /// ```
+    pub fn full_minify_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.config.full_minify_max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Sets [`MinifierConfig::fragment_header`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, HeaderName, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder()
+    ///     .fragment_header(HeaderName::from_static("x-html-fragment"))
+    ///     .build();
+    /// let fragment = "\n  <li class=\"done\">  Ship it  </li>\n  <script>let  x = 1 ;</script>\n";
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .header("x-html-fragment", "1")
+    ///     .body(Body::from(fragment))
+    ///     .unwrap();
+    ///
+    /// let response = minify_response(response, &config).await;
+    /// assert!(!response.headers().contains_key("x-html-fragment"));
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<li class=\"done\"> Ship it </li>\n<script>let  x = 1 ;</script>");
+    /// # });
+    /// ```
+    pub fn fragment_header(mut self, name: HeaderName) -> Self {
+        self.config.fragment_header = Some(name);
+        self
+    }
+
+    /// Sets [`MinifierConfig::fragment_content_types`].
+    pub fn fragment_content_types<I, T>(mut self, content_types: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.fragment_content_types = content_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::fragment_max_bytes`].
+    pub fn fragment_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.config.fragment_max_bytes = max_bytes;
+        self
+    }
+
+    /// Sets [`MinifierConfig::strict_content_length`].
+    pub fn strict_content_length(mut self, strict: bool) -> Self {
+        self.config.strict_content_length = strict;
+        self
+    }
+
+    /// Sets [`MinifierConfig::defer_content_length`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// // An inner layer already set a length, and another one appended it again
+    /// let page = || {
+    ///     Response::builder()
+    ///         .header(header::CONTENT_TYPE, "text/html")
+    ///         .header(header::CONTENT_LENGTH, "16")
+    ///         .header(header::CONTENT_LENGTH, "16")
+    ///         .body(Body::from("<p>  Hello  </p>"))
+    ///         .unwrap()
+    /// };
+    ///
+    /// let response = minify_response(page(), &MinifierConfig::default()).await;
+    /// let lengths: Vec<_> = response.headers().get_all(header::CONTENT_LENGTH).iter().collect();
+    /// assert_eq!(lengths, ["8"]);
+    ///
+    /// let config = MinifierConfig::builder().defer_content_length(true).build();
+    /// let response = minify_response(page(), &config).await;
+    /// assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+    /// # });
+    /// ```
+    pub fn defer_content_length(mut self, defer: bool) -> Self {
+        self.config.defer_content_length = defer;
+        self
+    }
+
+    /// Sets [`MinifierConfig::read_error_status`].
+    ///
+    /// ```
+    /// use std::{pin::Pin, task::{Context, Poll}};
+    ///
+    /// use axum::{body::{Body, Bytes}, http::{header, Response, StatusCode}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body::Frame;
+    /// use http_body_util::BodyExt;
+    ///
+    /// // A body whose upstream connection dropped
+    /// struct Broken;
+    ///
+    /// impl http_body::Body for Broken {
+    ///     type Data = Bytes;
+    ///     type Error = std::io::Error;
+    ///
+    ///     fn poll_frame(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+    ///         Poll::Ready(Some(Err(std::io::Error::other("upstream reset at 10.0.0.7"))))
+    ///     }
+    /// }
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder()
+    ///     .read_error_status(StatusCode::BAD_GATEWAY)
+    ///     .read_error_message("Something went wrong")
+    ///     .build();
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .body(Body::new(Broken))
+    ///     .unwrap();
+    ///
+    /// let response = minify_response(response, &config).await;
+    /// assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "Something went wrong");
+    /// # });
+    /// ```
+    pub fn read_error_status(mut self, status: StatusCode) -> Self {
+        self.config.read_error_status = Some(status);
+        self
+    }
+
+    /// Sets [`MinifierConfig::read_error_message`].
+    pub fn read_error_message(mut self, message: impl Into<String>) -> Self {
+        self.config.read_error_message = Some(message.into());
+        self
+    }
+
+    /// Sets [`MinifierConfig::opt_out_header`]; `None` disables opting out.
+    pub fn opt_out_header(mut self, header: Option<HeaderName>) -> Self {
+        self.config.opt_out_header = header;
+        self
+    }
+
+    /// Sets [`MinifierConfig::opt_out_query`]; `None` disables opting out.
+    ///
+    /// ```
+    /// use axum::{body::Body, http::Request, response::Html, routing::get, Router};
+    /// use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    /// use tower::Service;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let app = |config| Router::new()
+    ///     .route("/page", get(|| async { Html("<p>  Hello  </p>") }))
+    ///     .layer(HtmlMinifierLayer::with_config(config));
+    /// let get = |mut app: Router, uri: &'static str| async move {
+    ///     let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+    ///     app.call(request).await.unwrap().into_body().collect().await.unwrap().to_bytes()
+    /// };
+    ///
+    /// assert_eq!(get(app(MinifierConfig::default()), "/page").await, "<p>Hello");
+    /// assert_eq!(get(app(MinifierConfig::default()), "/page?minify=0").await, "<p>  Hello  </p>");
+    ///
+    /// // Locked down: the query is ignored
+    /// let locked = MinifierConfig::builder().opt_out_query(None).build();
+    /// assert_eq!(get(app(locked), "/page?minify=0").await, "<p>Hello");
+    /// # });
+    /// ```
+    pub fn opt_out_query(mut self, param: Option<&str>) -> Self {
+        self.config.opt_out_query = param.map(str::to_owned);
+        self
+    }
+
+    /// Sets [`MinifierConfig::bypass_user_agents`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Request}, response::Html, routing::get, Router};
+    /// use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    /// use tower::Service;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder().bypass_user_agents(["facebookexternalhit"]).build();
+    /// let mut app = Router::new()
+    ///     .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+    ///     .layer(HtmlMinifierLayer::with_config(config));
+    ///
+    /// let request = Request::builder()
+    ///     .header(header::USER_AGENT, "FacebookExternalHit/1.1 (+http://www.facebook.com/externalhit_uatext.php)")
+    ///     .body(Body::empty())
+    ///     .unwrap();
+    /// let response = app.call(request).await.unwrap();
+    /// assert!(response.headers().get_all(header::VARY).iter().any(|v| v == "user-agent"));
+    /// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<p>  Hello  </p>");
+    ///
+    /// let request = Request::builder().header(header::USER_AGENT, "Mozilla/5.0").body(Body::empty()).unwrap();
+    /// let body = app.call(request).await.unwrap().into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>Hello");
+    /// # });
+    /// ```
+    pub fn bypass_user_agents<I, T>(mut self, agents: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.bypass_user_agents = agents.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::methods`].
+    pub fn methods(mut self, methods: impl IntoIterator<Item = Method>) -> Self {
+        self.config.methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::skip_path_prefixes`].
+    pub fn skip_path_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.skip_path_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::download_path_prefixes`].
+    pub fn download_path_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.download_path_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::static_cache_control`].
+    ///
+    /// ```
+    /// use axum::{
+    ///     body::Body,
+    ///     http::{header, HeaderValue, Request},
+    ///     response::Html,
+    ///     routing::get,
+    ///     Router,
+    /// };
+    /// use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+    /// use tower::Service;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder()
+    ///     .static_cache_control(HeaderValue::from_static("public, max-age=31536000, immutable"))
+    ///     .static_path_prefixes(["/docs/"])
+    ///     .build();
+    /// let page = || async { Html("<p>  Hello  </p>") };
+    /// let mut app = Router::new()
+    ///     .route("/docs/intro", get(page))
+    ///     .route("/account", get(page))
+    ///     .layer(HtmlMinifierLayer::with_config(config));
+    ///
+    /// let request = Request::builder().uri("/docs/intro").body(Body::empty()).unwrap();
+    /// let response = app.call(request).await.unwrap();
+    /// assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=31536000, immutable");
+    ///
+    /// let request = Request::builder().uri("/account").body(Body::empty()).unwrap();
+    /// let response = app.call(request).await.unwrap();
+    /// assert!(!response.headers().contains_key(header::CACHE_CONTROL));
+    /// # });
+    /// ```
+    pub fn static_cache_control(mut self, value: HeaderValue) -> Self {
+        self.config.static_cache_control = Some(value);
+        self
+    }
+
+    /// Sets [`MinifierConfig::static_path_prefixes`].
+    pub fn static_path_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.static_path_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_error_responses`].
+    pub fn minify_error_responses(mut self, minify: bool) -> Self {
+        self.config.minify_error_responses = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::recompute_etag`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Request, StatusCode}, response::Html, routing::get, Router};
+    /// use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    /// use tower::Service;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder().recompute_etag(true).build();
+    /// let mut app = Router::new()
+    ///     .route("/", get(|| async { ([(header::ETAG, "\"v1\"")], Html("<p>  Hello  </p>")) }))
+    ///     .layer(HtmlMinifierLayer::with_config(config));
+    ///
+    /// let response = app.call(Request::new(Body::empty())).await.unwrap();
+    /// let etag = response.headers()[header::ETAG].clone();
+    ///
+    /// let request = Request::builder().header(header::IF_NONE_MATCH, etag).body(Body::empty()).unwrap();
+    /// let response = app.call(request).await.unwrap();
+    /// assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    /// assert!(response.into_body().collect().await.unwrap().to_bytes().is_empty());
+    /// # });
+    /// ```
+    pub fn recompute_etag(mut self, recompute: bool) -> Self {
+        self.config.recompute_etag = recompute;
+        self
+    }
+
+    /// Sets [`MinifierConfig::preload_links`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let html = r#"<html><head>
+    ///     <link rel="stylesheet" href="/app.css">
+    ///     <link rel="icon" href="/favicon.ico">
+    ///     <script type="module" src="/app.js"></script>
+    ///     <script nomodule src="/legacy.js"></script>
+    ///     <script src="/analytics.js?id=1&amp;v=2" defer></script>
+    ///     <script src="/chat.js" defer></script>
+    /// </head><body></body></html>"#;
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .body(Body::from(html))
+    ///     .unwrap();
+    ///
+    /// let config = MinifierConfig::builder().preload_links(3).build();
+    /// let response = minify_response(response, &config).await;
+    /// let links: Vec<_> = response.headers().get_all(header::LINK).iter().collect();
+    /// assert_eq!(links, [
+    ///     "</app.css>; rel=preload; as=style",
+    ///     "</app.js>; rel=modulepreload",
+    ///     "</analytics.js?id=1&v=2>; rel=preload; as=script",
+    /// ]);
+    /// # });
+    /// ```
+    pub fn preload_links(mut self, limit: usize) -> Self {
+        self.config.preload_links = limit;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_css_responses`].
+    pub fn minify_css_responses(mut self, minify: bool) -> Self {
+        self.config.minify_css_responses = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_js_responses`].
+    pub fn minify_js_responses(mut self, minify: bool) -> Self {
+        self.config.minify_js_responses = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_xml`].
+    pub fn minify_xml(mut self, minify: bool) -> Self {
+        self.config.minify_xml = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_json`].
+    #[cfg(feature = "json")]
+    pub fn minify_json(mut self, minify: bool) -> Self {
+        self.config.minify_json = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::json_html_pointers`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let json = r#"{"subject": "Hi  there", "email": {"html": "<p>  Hello,   world!  </p>", "text": "Hello,  world!"}}"#;
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "application/json")
+    ///     .body(Body::from(json))
+    ///     .unwrap();
+    ///
+    /// let config = MinifierConfig::builder().json_html_pointers(["/email/html"]).build();
+    /// let response = minify_response(response, &config).await;
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, r#"{"subject":"Hi  there","email":{"html":"<p>Hello, world!","text":"Hello,  world!"}}"#);
+    /// # });
+    /// ```
+    #[cfg(feature = "json")]
+    pub fn json_html_pointers<I, T>(mut self, pointers: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.json_html_pointers = pointers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_multipart`].
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let mut body = b"--sep\r\nContent-Type: text/html\r\nContent-Length: 20\r\n\r\n<p>  Hello  </p>    ".to_vec();
+    /// body.extend_from_slice(b"\r\n--sep\r\nContent-Type: image/png\r\n\r\n\x89PNG\r\n\x1a\n\xff\r\n--sep--\r\n");
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "multipart/mixed; boundary=sep")
+    ///     .body(Body::from(body))
+    ///     .unwrap();
+    ///
+    /// let config = MinifierConfig::builder().minify_multipart(true).build();
+    /// let response = minify_response(response, &config).await;
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// let mut expected = b"--sep\r\nContent-Type: text/html\r\nContent-Length: 8\r\n\r\n<p>Hello".to_vec();
+    /// expected.extend_from_slice(b"\r\n--sep\r\nContent-Type: image/png\r\n\r\n\x89PNG\r\n\x1a\n\xff\r\n--sep--\r\n");
+    /// assert_eq!(body, expected);
+    /// # });
+    /// ```
+    #[cfg(feature = "multipart")]
+    pub fn minify_multipart(mut self, minify: bool) -> Self {
+        self.config.minify_multipart = minify;
+        self
+    }
+
+    /// Sets [`MinifierConfig::compress`].
+    #[cfg(feature = "compress")]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.config.compress = compress;
+        self
+    }
+
+    /// Sets [`MinifierConfig::cache`].
+    #[cfg(feature = "cache")]
+    pub fn cache(mut self, cache: Arc<MinifyCache>) -> Self {
+        self.config.cache = Some(cache);
+        self
+    }
+
+    /// Sets [`MinifierConfig::minify_fn`].
+    ///
+    /// The function gets the `minify_html` settings derived from the rest of the config:
+    ///
+    /// ```
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// let config = MinifierConfig::builder()
+    ///     .minify_js(false)
+    ///     .keep_comments(true)
+    ///     .remove_bangs(false)
+    ///     .minify_fn(|input, cfg| {
+    ///         assert!(cfg.minify_css && !cfg.minify_js);
+    ///         assert!(cfg.keep_comments && !cfg.remove_bangs && cfg.remove_processing_instructions);
+    ///         assert!(cfg.allow_removing_spaces_between_attributes && !cfg.keep_closing_tags);
+    ///         input.to_vec()
+    ///     })
+    ///     .build();
+    /// minify_html_bytes(b"<p>Hello</p>", &config);
+    /// ```
+    pub fn minify_fn<F>(mut self, minify_fn: F) -> Self
+    where
+        F: Fn(&[u8], &Cfg) -> Vec<u8> + Send + Sync + 'static,
+    {
+        self.config.minify_fn = Some(Arc::new(minify_fn));
+        self
+    }
+
+    /// Sets [`MinifierConfig::timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets [`MinifierConfig::max_concurrent`] to a new semaphore with `limit` permits.
+    ///
+    /// ```
+    /// use std::sync::{mpsc, Mutex};
+    ///
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig, MinifyStats};
+    ///
+    /// fn page() -> Response<Body> {
+    ///     Response::builder()
+    ///         .header(header::CONTENT_TYPE, "text/html")
+    ///         .body(Body::from("<p>  Hello  </p>"))
+    ///         .unwrap()
+    /// }
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let (started, mut has_started) = tokio::sync::mpsc::unbounded_channel();
+    /// let (release, released) = mpsc::channel::<()>();
+    /// let released = Mutex::new(released);
+    /// let config = MinifierConfig::builder()
+    ///     .max_concurrent(1)
+    ///     // Holds on to its permit until released
+    ///     .minify_fn(move |input, cfg| {
+    ///         started.send(()).unwrap();
+    ///         released.lock().unwrap().recv().unwrap();
+    ///         minify_html::minify(input, cfg)
+    ///     })
+    ///     .build();
+    ///
+    /// let first = tokio::spawn({
+    ///     let config = config.clone();
+    ///     async move { minify_response(page(), &config).await }
+    /// });
+    /// has_started.recv().await.unwrap();
+    ///
+    /// // The only permit is taken, so this one is sent as it was
+    /// let second = minify_response(page(), &config).await;
+    /// assert!(!second.extensions().get::<MinifyStats>().unwrap().minified);
+    ///
+    /// release.send(()).unwrap();
+    /// let first = first.await.unwrap();
+    /// assert!(first.extensions().get::<MinifyStats>().unwrap().minified);
+    /// # });
+    /// ```
+    #[cfg(feature = "axum")]
+    pub fn max_concurrent(mut self, limit: usize) -> Self {
+        self.config.max_concurrent = Some(Arc::new(Semaphore::new(limit)));
+        self
+    }
+
+    /// Sets [`MinifierConfig::cpu_budget`] to allow `budget` of minification time every
+    /// `window`.
+    ///
+    /// ```
+    /// use std::{
+    ///     sync::{Arc, Mutex},
+    ///     thread,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig, MinifyStats, SkipReason};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let skipped = Arc::new(Mutex::new(Vec::new()));
+    /// let on_skip = skipped.clone();
+    /// let config = MinifierConfig::builder()
+    ///     .cpu_budget(Duration::from_millis(1), Duration::from_secs(3600))
+    ///     // Stands in for a large page, taking at least twice the whole budget
+    ///     .minify_fn(|input, cfg| {
+    ///         thread::sleep(Duration::from_millis(2));
+    ///         minify_html::minify(input, cfg)
+    ///     })
+    ///     .on_skip(move |reason| on_skip.lock().unwrap().push(reason))
+    ///     .build();
+    ///
+    /// let mut minified = Vec::new();
+    /// for page in 0..2 {
+    ///     let response = Response::builder()
+    ///         .header(header::CONTENT_TYPE, "text/html")
+    ///         .body(Body::from(format!("<p>  Page {page}  </p>")))
+    ///         .unwrap();
+    ///     let response = minify_response(response, &config).await;
+    ///     minified.push(response.extensions().get::<MinifyStats>().unwrap().minified);
+    /// }
+    ///
+    /// // The first page starts on a full budget and leaves it in debt for about an hour
+    /// assert_eq!(minified, [true, false]);
+    /// assert_eq!(*skipped.lock().unwrap(), [SkipReason::OverBudget]);
+    /// assert_eq!(config.cpu_budget.unwrap().remaining(), Duration::ZERO);
+    /// # });
+    /// ```
+    #[cfg(feature = "axum")]
+    pub fn cpu_budget(mut self, budget: Duration, window: Duration) -> Self {
+        self.config.cpu_budget = Some(Arc::new(CpuBudget::new(budget, window)));
+        self
+    }
+
+    /// Sets [`MinifierConfig::stats_collector`].
+    pub fn stats_collector(mut self, collector: Arc<MinifyStatsCollector>) -> Self {
+        self.config.stats_collector = Some(collector);
+        self
+    }
+
+    /// Sets [`MinifierConfig::on_skip`].
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig, SkipReason};
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let skipped = Arc::new(Mutex::new(Vec::new()));
+    /// let seen = skipped.clone();
+    /// let config = MinifierConfig::builder()
+    ///     .max_body_bytes(16)
+    ///     .on_skip(move |reason| seen.lock().unwrap().push(reason))
+    ///     .build();
+    /// for body in ["<p>  Hello  </p>", "<p>  Hello, world!  </p>"] {
+    ///     let response = Response::builder().header(header::CONTENT_TYPE, "text/html").body(Body::from(body)).unwrap();
+    ///     minify_response(response, &config).await;
+    /// }
+    /// assert_eq!(*skipped.lock().unwrap(), [SkipReason::TooLarge]);
+    /// # });
+    /// ```
+    pub fn on_skip<F>(mut self, on_skip: F) -> Self
+    where
+        F: Fn(SkipReason) + Send + Sync + 'static,
+    {
+        self.config.on_skip = Some(Arc::new(on_skip));
+        self
+    }
+
+    /// Sets [`MinifierConfig::pre_process`].
+    pub fn pre_process<F>(mut self, pre_process: F) -> Self
+    where
+        F: Fn(Bytes) -> Bytes + Send + Sync + 'static,
+    {
+        self.config.pre_process = Some(Arc::new(pre_process));
+        self
+    }
+
+    /// Sets [`MinifierConfig::post_process`].
+    ///
+    /// The stages of a buffered body always run in the same order: `pre_process`, the
+    /// minifier, `post_process`, the headers describing the body, and compression last.
+    ///
+    /// ```
+    /// use axum::{body::Body, http::{header, Response}};
+    /// use axum_mini::{minify_response, MinifierConfig};
+    /// use http_body_util::BodyExt;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let config = MinifierConfig::builder()
+    ///     // Before the minifier, so the injected markup is minified too
+    ///     .pre_process(|body| [&body[..], b"<footer>  Built  today  </footer>"].concat().into())
+    ///     .post_process(|body, headers| {
+    ///         // After the minifier, but before the headers describing the body are set
+    ///         assert_eq!(body, "<p>Hello<footer>Built today</footer>");
+    ///         assert!(!headers.contains_key("x-minified-bytes"));
+    ///         [&body[..], b"<!--  build 42  -->"].concat().into()
+    ///     })
+    ///     .debug_headers(true)
+    ///     .build();
+    /// let response = Response::builder()
+    ///     .header(header::CONTENT_TYPE, "text/html")
+    ///     .header(header::CONTENT_LENGTH, "16")
+    ///     .body(Body::from("<p>  Hello  </p>"))
+    ///     .unwrap();
+    /// let response = minify_response(response, &config).await;
+    ///
+    /// // The headers describe the post-processed body
+    /// assert_eq!(response.headers()["x-minified-bytes"], "55");
+    /// assert_eq!(response.headers()[header::CONTENT_LENGTH], "55");
+    /// let body = response.into_body().collect().await.unwrap().to_bytes();
+    /// assert_eq!(body, "<p>Hello<footer>Built today</footer><!--  build 42  -->");
+    /// # });
+    /// ```
+    pub fn post_process<F>(mut self, post_process: F) -> Self
+    where
+        F: Fn(Bytes, &mut HeaderMap) -> Bytes + Send + Sync + 'static,
+    {
+        self.config.post_process = Some(Arc::new(post_process));
+        self
+    }
+
+    /// Sets [`MinifierConfig::externalize_styles`].
+    ///
+    /// ```
+    /// use std::{
+    ///     collections::HashMap,
+    ///     hash::{DefaultHasher, Hash, Hasher},
+    ///     sync::{Arc, Mutex},
+    /// };
+    ///
+    /// use axum_mini::{minify_html_bytes, MinifierConfig};
+    ///
+    /// // Stands in for e.g. a CDN upload; keyed by content, so pages sharing a style share a URL
+    /// let store = Arc::new(Mutex::new(HashMap::new()));
+    /// let stored = store.clone();
+    /// let config = MinifierConfig::builder()
+    ///     .externalize_styles(move |css| {
+    ///         let mut hasher = DefaultHasher::new();
+    ///         css.hash(&mut hasher);
+    ///         let url = format!("/assets/{:x}.css", hasher.finish());
+    ///         stored.lock().unwrap().insert(url.clone(), css.to_owned());
+    ///         Some(url)
+    ///     })
+    ///     .externalize_styles_min_bytes(64)
+    ///     .build();
+    ///
+    /// let rules: String = (0..8).map(|i| format!(".col-{i} {{ width: {}%; }}\n", i * 10)).collect();
+    /// let page = format!(
+    ///     "<head><style media=print>\n{rules}</style><style>p {{ margin: 0 }}</style></head><p>Hi</p>"
+    /// );
+    /// let minified = String::from_utf8(minify_html_bytes(page.as_bytes(), &config)).unwrap();
+    ///
+    /// let store = store.lock().unwrap();
+    /// let (url, css) = store.iter().next().unwrap();
+    /// assert_eq!(store.len(), 1);
+    /// assert!(css.starts_with(".col-0{width:0%}.col-1{width:10%}"));
+    /// // The small style stays inline
+    /// assert_eq!(minified, format!(r#"<link rel=stylesheet href="{url}" media=print><style>p{{margin:0}}</style></head><p>Hi"#));
+    /// ```
+    pub fn externalize_styles<F>(mut self, store: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.config.externalize_styles = Some(Arc::new(store));
+        self
+    }
+
+    /// Sets [`MinifierConfig::externalize_styles_min_bytes`].
+    pub fn externalize_styles_min_bytes(mut self, bytes: usize) -> Self {
+        self.config.externalize_styles_min_bytes = bytes;
+        self
+    }
+
+    /// Finishes the configuration.
+    pub fn build(self) -> MinifierConfig {
+        self.config
+    }
+
+    /// Finishes the configuration, failing if [`MinifierConfig::validate`] finds a problem.
+    ///
+    /// ```
+    /// use axum_mini::{ConfigError, MinifierConfig};
+    ///
+    /// let result = MinifierConfig::builder().methods([]).try_build();
+    /// assert_eq!(result.unwrap_err(), ConfigError::NoMethods);
+    /// ```
+    pub fn try_build(self) -> Result<MinifierConfig, ConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
@@ -0,0 +1,136 @@
+//! Classification of responses by `Content-Type`.
+
+#[cfg(feature = "axum")]
+use http::{header::CONTENT_TYPE, HeaderMap, HeaderValue};
+#[cfg(feature = "axum")]
+use mime::Mime;
+
+#[cfg(feature = "axum")]
+use crate::MinifierConfig;
+
+/// Content types that identify a JavaScript response.
+pub(crate) const JS_CONTENT_TYPES: [&str; 2] = ["application/javascript", "text/javascript"];
+
+/// Content types that identify an XML or SVG response.
+#[cfg(feature = "axum")]
+const XML_CONTENT_TYPES: [&str; 3] = ["application/xml", "text/xml", "image/svg+xml"];
+
+/// Kind of document a response carries, selecting how it gets minified.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ContentTypeClass {
+    /// One of [`MinifierConfig::content_types`], a `+html` type with
+    /// [`MinifierConfig::minify_html_suffix_types`], or an empty type with
+    /// [`MinifierConfig::default_to_html_when_empty`].
+    Html,
+    /// `text/css`.
+    Css,
+    /// `application/javascript` or `text/javascript`.
+    Js,
+    /// `application/xml`, `text/xml` or `image/svg+xml`.
+    Xml,
+    /// `application/json`.
+    #[cfg(feature = "json")]
+    Json,
+    /// `multipart/*` with a boundary; only its HTML parts are minified.
+    #[cfg(feature = "multipart")]
+    Multipart,
+}
+
+impl ContentTypeClass {
+    /// Short lowercase name, e.g. for metric labels.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            Self::Html => "html",
+            Self::Css => "css",
+            Self::Js => "js",
+            Self::Xml => "xml",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+            #[cfg(feature = "multipart")]
+            Self::Multipart => "multipart",
+        }
+    }
+}
+
+/// Classifies a response by its `Content-Type`, or `None` if it shouldn't be minified.
+#[cfg(feature = "axum")]
+pub(crate) fn classify(cfg: &MinifierConfig, headers: &HeaderMap) -> Option<ContentTypeClass> {
+    if cfg.default_to_html_when_empty && is_blank(headers) {
+        return Some(ContentTypeClass::Html);
+    }
+    let content_type = content_type(headers)?;
+    let essence = content_type.essence_str();
+    let is = |ty: &str| essence.eq_ignore_ascii_case(ty);
+
+    if cfg.content_types.iter().chain(&cfg.fragment_content_types).any(|allowed| is(allowed)) {
+        return Some(ContentTypeClass::Html);
+    }
+    if cfg.minify_html_suffix_types && content_type.suffix().is_some_and(|suffix| suffix == "html") {
+        return Some(ContentTypeClass::Html);
+    }
+    if cfg.minify_css_responses && is("text/css") {
+        return Some(ContentTypeClass::Css);
+    }
+    if cfg.minify_js_responses && JS_CONTENT_TYPES.iter().any(|js| is(js)) {
+        return Some(ContentTypeClass::Js);
+    }
+    if cfg.minify_xml && XML_CONTENT_TYPES.iter().any(|xml| is(xml)) {
+        return Some(ContentTypeClass::Xml);
+    }
+    #[cfg(feature = "json")]
+    if (cfg.minify_json || !cfg.json_html_pointers.is_empty()) && is("application/json") {
+        return Some(ContentTypeClass::Json);
+    }
+    #[cfg(feature = "multipart")]
+    if cfg.minify_multipart && content_type.type_() == mime::MULTIPART && content_type.get_param(mime::BOUNDARY).is_some() {
+        return Some(ContentTypeClass::Multipart);
+    }
+    None
+}
+
+/// Whether the response's type is one of [`MinifierConfig::fragment_content_types`].
+#[cfg(feature = "axum")]
+pub(crate) fn is_fragment_type(cfg: &MinifierConfig, headers: &HeaderMap) -> bool {
+    content_type(headers).is_some_and(|ty| {
+        cfg.fragment_content_types.iter().any(|fragment| ty.essence_str().eq_ignore_ascii_case(fragment))
+    })
+}
+
+/// Whether the response is a stream of server-sent events.
+#[cfg(feature = "axum")]
+pub(crate) fn is_event_stream(headers: &HeaderMap) -> bool {
+    content_type(headers).is_some_and(|ty| ty.essence_str().eq_ignore_ascii_case("text/event-stream"))
+}
+
+/// Whether a body without a `Content-Type` starts like an HTML document.
+pub(crate) fn looks_like_html(body: &[u8]) -> bool {
+    let body = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let start = body.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(body.len());
+    let starts_with = |marker: &[u8]| {
+        body.get(start..start + marker.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(marker))
+    };
+    starts_with(b"<!doctype html") || starts_with(b"<html")
+}
+
+/// Whether the response has a `Content-Type` header, but only empty or whitespace values.
+#[cfg(feature = "axum")]
+fn is_blank(headers: &HeaderMap) -> bool {
+    let mut values = headers.get_all(CONTENT_TYPE).iter().peekable();
+    values.peek().is_some() && values.all(|value| value.as_bytes().trim_ascii().is_empty())
+}
+
+/// Parses the `Content-Type` header.
+///
+/// Returns `None` if it is missing, malformed, or repeated with disagreeing types.
+#[cfg(feature = "axum")]
+pub(crate) fn content_type(headers: &HeaderMap) -> Option<Mime> {
+    let parse = |value: &HeaderValue| value.to_str().ok()?.trim().parse::<Mime>().ok();
+    let mut values = headers.get_all(CONTENT_TYPE).iter();
+    let first = parse(values.next()?)?;
+    values
+        .all(|value| parse(value).is_some_and(|other| other.essence_str().eq_ignore_ascii_case(first.essence_str())))
+        .then_some(first)
+}
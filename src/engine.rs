@@ -0,0 +1,224 @@
+//! The single point of contact with `minify_html`'s configuration.
+//!
+//! `Cfg` fields have been renamed between `minify_html` releases, so the rest of the crate
+//! never reads or writes them directly; upgrading `minify_html` only has to touch this module.
+
+#[cfg(feature = "cache")]
+use std::hash::{Hash, Hasher};
+
+use minify_html::Cfg;
+
+use crate::MinifierConfig;
+
+/// Builds the `minify_html` configuration for `config`.
+///
+/// [`MinifierConfig::minify_html_cfg`] is used verbatim when set.
+pub(crate) fn cfg(config: &MinifierConfig) -> Cfg {
+    if let Some(cfg) = &config.minify_html_cfg {
+        return cfg.clone();
+    }
+    let mut cfg = Cfg::new();
+    cfg.allow_removing_spaces_between_attributes = config.allow_removing_spaces_between_attributes;
+    cfg.keep_closing_tags = !config.remove_optional_closing_tags;
+    cfg.minify_css = config.minify_css;
+    cfg.minify_js = config.minify_js;
+    cfg.remove_bangs = config.remove_bangs;
+    cfg.remove_processing_instructions = config.remove_processing_instructions;
+    cfg.keep_comments = config.keep_comments;
+    cfg
+}
+
+/// A configuration that only minifies JavaScript.
+pub(crate) fn js_only() -> Cfg {
+    let mut cfg = Cfg::new();
+    cfg.minify_js = true;
+    cfg
+}
+
+/// `cfg` with CSS minification switched on.
+#[cfg(feature = "axum")]
+pub(crate) fn with_css(cfg: &Cfg) -> Cfg {
+    let mut cfg = cfg.clone();
+    cfg.minify_css = true;
+    cfg
+}
+
+/// `cfg` with JavaScript minification switched on.
+#[cfg(feature = "axum")]
+pub(crate) fn with_js(cfg: &Cfg) -> Cfg {
+    let mut cfg = cfg.clone();
+    cfg.minify_js = true;
+    cfg
+}
+
+/// Makes `cfg` keep closing tags, even optional ones.
+pub(crate) fn keep_closing_tags(cfg: &mut Cfg) {
+    cfg.keep_closing_tags = true;
+}
+
+/// Makes `cfg` leave alone what AMP validation depends on: closing tags, the `<html>` and
+/// `<head>` start tags, inline scripts and the spaces between attributes.
+pub(crate) fn amp_safe(cfg: &mut Cfg) {
+    cfg.keep_closing_tags = true;
+    cfg.keep_html_and_head_opening_tags = true;
+    cfg.minify_js = false;
+    cfg.allow_removing_spaces_between_attributes = false;
+}
+
+/// Whether `cfg` keeps comments.
+pub(crate) fn keeps_comments(cfg: &Cfg) -> bool {
+    cfg.keep_comments
+}
+
+/// Feeds every setting of `cfg` to `state`.
+#[cfg(feature = "cache")]
+pub(crate) fn hash_cfg(cfg: &Cfg, state: &mut impl Hasher) {
+    [
+        cfg.allow_noncompliant_unquoted_attribute_values,
+        cfg.allow_optimal_entities,
+        cfg.allow_removing_spaces_between_attributes,
+        cfg.keep_closing_tags,
+        cfg.keep_comments,
+        cfg.keep_html_and_head_opening_tags,
+        cfg.keep_input_type_text_attr,
+        cfg.keep_ssi_comments,
+        cfg.minify_css,
+        cfg.minify_doctype,
+        cfg.minify_js,
+        cfg.preserve_brace_template_syntax,
+        cfg.preserve_chevron_percent_template_syntax,
+        cfg.remove_bangs,
+        cfg.remove_processing_instructions,
+    ]
+    .hash(state);
+}
+
+/// Minifies an HTML document.
+pub(crate) fn minify(input: &[u8], cfg: &Cfg) -> Vec<u8> {
+    minify_html::minify(input, cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that setting a `MinifierConfig` flag with `set` sets the `Cfg` field read by
+    /// `get` to the same value, or the opposite one if `inverted`.
+    fn assert_maps(set: fn(&mut MinifierConfig, bool), get: fn(&Cfg) -> bool, inverted: bool) {
+        for value in [false, true] {
+            let mut config = MinifierConfig::default();
+            set(&mut config, value);
+            assert_eq!(get(&cfg(&config)), value != inverted, "with the flag set to {value}");
+        }
+    }
+
+    #[test]
+    fn allow_removing_spaces_between_attributes_maps_directly() {
+        assert_maps(
+            |config, value| config.allow_removing_spaces_between_attributes = value,
+            |cfg| cfg.allow_removing_spaces_between_attributes,
+            false,
+        );
+    }
+
+    #[test]
+    fn remove_optional_closing_tags_maps_to_not_keeping_them() {
+        assert_maps(|config, value| config.remove_optional_closing_tags = value, |cfg| cfg.keep_closing_tags, true);
+    }
+
+    #[test]
+    fn minify_css_maps_directly() {
+        assert_maps(|config, value| config.minify_css = value, |cfg| cfg.minify_css, false);
+    }
+
+    #[test]
+    fn minify_js_maps_directly() {
+        assert_maps(|config, value| config.minify_js = value, |cfg| cfg.minify_js, false);
+    }
+
+    #[test]
+    fn remove_bangs_maps_directly() {
+        assert_maps(|config, value| config.remove_bangs = value, |cfg| cfg.remove_bangs, false);
+    }
+
+    #[test]
+    fn remove_processing_instructions_maps_directly() {
+        assert_maps(
+            |config, value| config.remove_processing_instructions = value,
+            |cfg| cfg.remove_processing_instructions,
+            false,
+        );
+    }
+
+    #[test]
+    fn keep_comments_maps_directly() {
+        assert_maps(|config, value| config.keep_comments = value, |cfg| cfg.keep_comments, false);
+        assert!(keeps_comments(&cfg(&MinifierConfig { keep_comments: true, ..MinifierConfig::default() })));
+    }
+
+    #[test]
+    fn unmapped_fields_keep_minify_html_defaults() {
+        let cfg = cfg(&MinifierConfig::default());
+        assert!(!cfg.keep_html_and_head_opening_tags);
+        assert!(!cfg.keep_input_type_text_attr);
+        assert!(!cfg.keep_ssi_comments);
+        assert!(!cfg.minify_doctype);
+        assert!(!cfg.preserve_brace_template_syntax);
+        assert!(!cfg.preserve_chevron_percent_template_syntax);
+    }
+
+    #[test]
+    fn minify_html_cfg_is_used_verbatim() {
+        let mut custom = Cfg::new();
+        custom.keep_ssi_comments = true;
+        let config = MinifierConfig {
+            minify_html_cfg: Some(custom),
+            minify_css: true,
+            keep_comments: true,
+            ..MinifierConfig::default()
+        };
+        let cfg = cfg(&config);
+        assert!(cfg.keep_ssi_comments);
+        assert!(!cfg.minify_css);
+        assert!(!cfg.keep_comments);
+    }
+
+    #[test]
+    fn js_only_minifies_nothing_but_js() {
+        let cfg = js_only();
+        assert!(cfg.minify_js);
+        assert!(!cfg.minify_css);
+        assert!(!cfg.keep_closing_tags);
+    }
+
+    #[cfg(feature = "axum")]
+    #[test]
+    fn with_css_and_with_js_only_switch_their_language_on() {
+        let mut base = Cfg::new();
+        base.keep_comments = true;
+        let css = with_css(&base);
+        assert!(css.minify_css && !css.minify_js && css.keep_comments);
+        let js = with_js(&base);
+        assert!(js.minify_js && !js.minify_css && js.keep_comments);
+    }
+
+    #[test]
+    fn keep_closing_tags_sets_only_that_flag() {
+        let mut cfg = cfg(&MinifierConfig::default());
+        keep_closing_tags(&mut cfg);
+        assert!(cfg.keep_closing_tags);
+        assert!(cfg.minify_css && cfg.minify_js);
+    }
+
+    #[test]
+    fn amp_safe_keeps_what_amp_validation_needs() {
+        let mut cfg = cfg(&MinifierConfig::default());
+        amp_safe(&mut cfg);
+        assert!(cfg.keep_closing_tags);
+        assert!(cfg.keep_html_and_head_opening_tags);
+        assert!(!cfg.minify_js);
+        assert!(!cfg.allow_removing_spaces_between_attributes);
+        // CSS is still minified; AMP only limits its size
+        assert!(cfg.minify_css);
+    }
+}
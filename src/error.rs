@@ -0,0 +1,123 @@
+//! Errors returned by the minifier middleware, and for invalid configurations.
+
+use std::{error::Error, fmt};
+
+#[cfg(feature = "axum")]
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+use crate::ContentTypeClass;
+
+/// Error produced when a response can't be passed through the minifier.
+///
+/// As a response it renders as a `500 Internal Server Error`, except for
+/// [`MinifyError::TooLarge`], which renders as `413 Payload Too Large`. The middleware
+/// sends a response of its own instead of [`MinifyError::BodyRead`] when
+/// [`MinifierConfig::read_error_status`](crate::MinifierConfig::read_error_status) or
+/// [`MinifierConfig::read_error_message`](crate::MinifierConfig::read_error_message) is set.
+#[derive(Debug)]
+pub enum MinifyError {
+    /// Reading the response body failed.
+    BodyRead(String),
+    /// The response body exceeded the configured size limit, and
+    /// [`MinifierConfig::reject_too_large`](crate::MinifierConfig::reject_too_large) is set.
+    TooLarge,
+    /// The minifier panicked.
+    MinifyPanic,
+    /// The minifier ran past [`MinifierConfig::timeout`](crate::MinifierConfig::timeout).
+    Timeout,
+    /// Every [`MinifierConfig::max_concurrent`](crate::MinifierConfig::max_concurrent) permit
+    /// was taken.
+    Busy,
+    /// The [`MinifierConfig::cpu_budget`](crate::MinifierConfig::cpu_budget) was spent.
+    OverBudget,
+    /// The minified body lost visible text, as found with
+    /// [`MinifierConfig::verify_rate`](crate::MinifierConfig::verify_rate).
+    Diverged,
+    /// The [`MinifierConfig::shutdown`](crate::MinifierConfig::shutdown) flag was set before
+    /// minification started.
+    ShuttingDown,
+}
+
+impl fmt::Display for MinifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BodyRead(err) => write!(f, "failed to read response body: {err}"),
+            Self::TooLarge => f.write_str("response body too large"),
+            Self::MinifyPanic => f.write_str("minification panicked"),
+            Self::Timeout => f.write_str("minification timed out"),
+            Self::Busy => f.write_str("too many concurrent minifications"),
+            Self::OverBudget => f.write_str("minification CPU budget spent"),
+            Self::Diverged => f.write_str("minified body lost visible text"),
+            Self::ShuttingDown => f.write_str("server is shutting down"),
+        }
+    }
+}
+
+impl Error for MinifyError {}
+
+#[cfg(feature = "axum")]
+impl IntoResponse for MinifyError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            Self::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+/// Contradictory or meaningless settings found by [`MinifierConfig::validate`](crate::MinifierConfig::validate).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// `min_size_bytes` is larger than `max_body_bytes`, so no body is ever minified.
+    MinSizeAboveMaxBody {
+        /// The configured `min_size_bytes`.
+        min_size_bytes: usize,
+        /// The configured `max_body_bytes`.
+        max_body_bytes: usize,
+    },
+    /// No content type is enabled, so no body is ever minified.
+    NoContentTypes,
+    /// `methods` is empty, so no response is ever minified.
+    NoMethods,
+    /// A fraction setting is outside `0.0..=1.0`.
+    RateOutOfRange {
+        /// Name of the setting.
+        name: &'static str,
+        /// The configured value.
+        value: f64,
+    },
+    /// `reject_too_large` is set without a `max_body_bytes` to enforce.
+    RejectWithoutLimit,
+    /// `max_concurrent` has no permits, so every body is sent unminified.
+    NoConcurrency,
+    /// An entry of `per_content_type` is invalid.
+    PerContentType {
+        /// The class the entry applies to.
+        class: ContentTypeClass,
+        /// What is wrong with it.
+        error: Box<ConfigError>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinSizeAboveMaxBody { min_size_bytes, max_body_bytes } => write!(
+                f,
+                "min_size_bytes ({min_size_bytes}) is larger than max_body_bytes ({max_body_bytes}), so nothing would be minified"
+            ),
+            Self::NoContentTypes => f.write_str("no content type is enabled, so nothing would be minified"),
+            Self::NoMethods => f.write_str("methods is empty, so nothing would be minified"),
+            Self::RateOutOfRange { name, value } => write!(f, "{name} ({value}) must be between 0.0 and 1.0"),
+            Self::RejectWithoutLimit => f.write_str("reject_too_large is set, but max_body_bytes is not"),
+            Self::NoConcurrency => f.write_str("max_concurrent has no permits, so nothing would be minified"),
+            Self::PerContentType { class, error } => write!(f, "per_content_type entry for {class:?}: {error}"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
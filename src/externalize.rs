@@ -0,0 +1,85 @@
+//! Moving large inline stylesheets out of a minified document.
+
+use crate::{
+    attrs::attributes,
+    protect::{find, find_ignore_case, matching_close, opens_tag, raw_text_end, tag_end, Stash},
+    ExternalizeStylesFn,
+};
+
+/// Elements whose `<style>` children can't become a `<link>`: SVG styles the drawing, template
+/// content isn't rendered as it stands, and `<noscript>` only applies without scripts.
+const CONTAINERS: [&str; 3] = ["svg", "template", "noscript"];
+
+/// Replaces every `<style>` element of at least `min_bytes` of CSS with a `<link>` to the URL
+/// `store` returns for it. Styles `store` declines, and styles with attributes other than
+/// `type` and `media`, stay inline.
+pub(crate) fn styles(html: &[u8], min_bytes: usize, store: &ExternalizeStylesFn) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    let mut i = 0;
+    while let Some(lt) = find(html, b"<", i) {
+        if html[lt..].starts_with(b"<!--") {
+            i = find(html, b"-->", lt + 4).map_or(html.len(), |close| close + 3);
+        } else if let Some(container) = CONTAINERS.iter().find(|tag| opens_tag(html, lt, tag)) {
+            let Some(content_start) = tag_end(html, lt) else { break };
+            i = matching_close(html, container, content_start).unwrap_or(html.len());
+        } else if opens_tag(html, lt, "style") {
+            let Some(content_start) = tag_end(html, lt) else { break };
+            let Some(content_end) = find_ignore_case(html, b"</style", content_start) else { break };
+            let end = raw_text_end(html, lt).unwrap_or(html.len());
+            if let Some(link) = link(html, lt, content_start, &html[content_start..content_end], min_bytes, store) {
+                out.extend_from_slice(&html[flushed..lt]);
+                out.extend_from_slice(&link);
+                flushed = end;
+            }
+            i = end;
+        } else if let Some(end) = raw_text_end(html, lt) {
+            i = end;
+        } else {
+            i = lt + 1;
+        }
+    }
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+/// The `<link>` replacing the `<style>` starting at `lt`, if it should be externalized.
+fn link(
+    html: &[u8],
+    lt: usize,
+    start_end: usize,
+    css: &[u8],
+    min_bytes: usize,
+    store: &ExternalizeStylesFn,
+) -> Option<Vec<u8>> {
+    // A protected region's placeholder isn't CSS
+    if css.len() < min_bytes || !Stash::can_protect(css) {
+        return None;
+    }
+    let mut media = None;
+    for attr in attributes(html, lt, start_end) {
+        let name = &html[attr.name];
+        if name.eq_ignore_ascii_case(b"media") {
+            media = Some(&html[attr.span]);
+        } else if !name.eq_ignore_ascii_case(b"type") {
+            return None;
+        }
+    }
+    let url = store(std::str::from_utf8(css).ok()?)?;
+
+    let mut link = b"<link rel=stylesheet href=\"".to_vec();
+    for byte in url.bytes() {
+        match byte {
+            b'&' => link.extend_from_slice(b"&amp;"),
+            b'"' => link.extend_from_slice(b"&quot;"),
+            _ => link.push(byte),
+        }
+    }
+    link.push(b'"');
+    if let Some(media) = media {
+        link.push(b' ');
+        link.extend_from_slice(media);
+    }
+    link.push(b'>');
+    Some(link)
+}
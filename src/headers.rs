@@ -0,0 +1,102 @@
+//! Helpers for response headers affected by rewriting the body.
+
+use axum::http::{
+    header::{ACCEPT_RANGES, CACHE_CONTROL, CONNECTION, CONTENT_DISPOSITION, CONTENT_ENCODING, ETAG, VARY},
+    HeaderMap, HeaderName, HeaderValue,
+};
+
+/// Computes a weak ETag for `body`.
+pub(crate) fn weak_etag(body: &[u8]) -> HeaderValue {
+    let tag = format!("W/\"{:x}-{:016x}\"", body.len(), content_hash(body));
+    HeaderValue::try_from(tag).expect("hex digits are a valid header value")
+}
+
+/// Turns a strong ETag into a weak one, for bodies that changed but are equivalent.
+pub(crate) fn weaken_etag(headers: &mut HeaderMap) {
+    let Some(etag) = headers.get(ETAG) else { return };
+    if etag.as_bytes().starts_with(b"W/") {
+        return;
+    }
+    let mut weak = b"W/".to_vec();
+    weak.extend_from_slice(etag.as_bytes());
+    if let Ok(weak) = HeaderValue::from_bytes(&weak) {
+        headers.insert(ETAG, weak);
+    }
+}
+
+/// Withdraws an `Accept-Ranges` offer. File services answer ranges from the file as stored,
+/// so a range of the original spliced into a minified copy would be garbage.
+pub(crate) fn refuse_ranges(headers: &mut HeaderMap) {
+    if headers.contains_key(ACCEPT_RANGES) {
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("none"));
+    }
+}
+
+/// Whether an `If-None-Match` value matches `etag`, using the weak comparison.
+pub(crate) fn etag_matches(if_none_match: &HeaderValue, etag: &HeaderValue) -> bool {
+    fn opaque(tag: &str) -> &str {
+        let tag = tag.trim();
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+    let (Ok(tags), Ok(etag)) = (if_none_match.to_str(), etag.to_str()) else { return false };
+    tags.split(',').any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
+
+/// Hashes `body` with 64-bit FNV-1a, which is stable across processes and Rust versions.
+pub(crate) fn content_hash(body: &[u8]) -> u64 {
+    body.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Whether `Content-Encoding` names a coding other than `identity`, making the body opaque
+/// bytes to the minifier.
+pub(crate) fn is_encoded(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .any(|v| !v.as_bytes().eq_ignore_ascii_case(b"identity"))
+}
+
+/// Whether `Cache-Control` forbids intermediaries from transforming the payload.
+pub(crate) fn is_no_transform(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-transform"))
+}
+
+/// Whether `Content-Disposition` marks the body as a download rather than a page.
+pub(crate) fn is_attachment(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("attachment"))
+}
+
+/// Whether `Connection` announces a protocol upgrade, e.g. to a WebSocket.
+pub(crate) fn is_upgrade(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CONNECTION)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|option| option.trim().eq_ignore_ascii_case("upgrade"))
+}
+
+/// Adds `name` to the response's `Vary` header unless it is already covered.
+pub(crate) fn append_vary(headers: &mut HeaderMap, name: &HeaderName) {
+    let covered = headers
+        .get_all(VARY)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|listed| listed == "*" || listed.eq_ignore_ascii_case(name.as_str()));
+    if !covered {
+        headers.append(VARY, HeaderValue::from_name(name.clone()));
+    }
+}
@@ -0,0 +1,66 @@
+//! Whitespace stripping for JSON responses, and minification of the HTML they carry.
+
+use serde::de::IgnoredAny;
+use serde_json::Value;
+
+use crate::{minify_html_bytes, MinifierConfig};
+
+/// Removes insignificant whitespace from a JSON document.
+///
+/// The document is only parsed to validate it; the output is the input with whitespace
+/// outside strings dropped, so key order, numbers and escapes keep their exact spelling.
+/// Invalid JSON is returned unchanged.
+pub(crate) fn minify(input: &[u8]) -> Vec<u8> {
+    if serde_json::from_slice::<IgnoredAny>(input).is_err() {
+        return input.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(input.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for &byte in input {
+        if in_string {
+            out.push(byte);
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+        } else if byte == b'"' {
+            in_string = true;
+            out.push(byte);
+        } else if !matches!(byte, b' ' | b'\t' | b'\n' | b'\r') {
+            out.push(byte);
+        }
+    }
+    out
+}
+
+/// Minifies the HTML strings at `pointers`, writing the document back compactly.
+///
+/// Keys keep their order and every other value is kept as parsed. Invalid JSON, or a document
+/// none of whose pointers lead to a string, is returned unchanged.
+pub(crate) fn minify_html_fields(input: &[u8], pointers: &[String], cfg: &MinifierConfig) -> Vec<u8> {
+    if pointers.is_empty() {
+        return input.to_vec();
+    }
+    let Ok(mut document) = serde_json::from_slice::<Value>(input) else {
+        return input.to_vec();
+    };
+    let mut found = false;
+    for pointer in pointers {
+        if let Some(Value::String(html)) = document.pointer_mut(pointer) {
+            // Minifying valid UTF-8 yields valid UTF-8, but a custom minifier might not
+            if let Ok(minified) = String::from_utf8(minify_html_bytes(html.as_bytes(), cfg)) {
+                *html = minified;
+            }
+            found = true;
+        }
+    }
+    if !found {
+        return input.to_vec();
+    }
+    serde_json::to_vec(&document).unwrap_or_else(|_| input.to_vec())
+}
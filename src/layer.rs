@@ -0,0 +1,208 @@
+//! `tower::Layer` implementation of the HTML minifier.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::Body,
+    http::{Request, Response},
+    response::IntoResponse,
+};
+use tower::{Layer, Service};
+
+use crate::{
+    middleware::{process_response, RequestInfo},
+    overrides, MinifierConfig,
+};
+
+/// Layer that minifies HTML responses of the wrapped service.
+///
+/// ```
+/// use axum::Router;
+/// use axum_mini::HtmlMinifierLayer;
+///
+/// let app: Router = Router::new()
+///     .route("/", axum::routing::get(|| async { "<h1>Hello World!</h1>" }))
+///     .layer(HtmlMinifierLayer::new());
+/// ```
+///
+/// With a compression layer such as `tower_http`'s `CompressionLayer`, add the minifier
+/// first so that it runs inside the compression and sees the plain body. In the opposite
+/// order it gets compressed bytes, which it recognizes by their `Content-Encoding` and
+/// forwards untouched. Here a stand-in compressor just marks the body:
+///
+/// ```
+/// use axum::{
+///     body::Body,
+///     http::{header, HeaderValue, Request},
+///     middleware,
+///     response::{Html, Response},
+///     routing::get,
+///     Router,
+/// };
+/// use axum_mini::HtmlMinifierLayer;
+/// use http_body_util::BodyExt;
+/// use tower::Service;
+///
+/// async fn compress(response: Response) -> Response {
+///     let (mut parts, body) = response.into_parts();
+///     let body = body.collect().await.unwrap().to_bytes();
+///     parts.headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+///     Response::from_parts(parts, Body::from([b"gz:".as_slice(), &body].concat()))
+/// }
+///
+/// async fn body_of(mut app: Router) -> String {
+///     let response = app.call(Request::new(Body::empty())).await.unwrap();
+///     String::from_utf8(response.into_body().collect().await.unwrap().to_bytes().to_vec()).unwrap()
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let router = || Router::new().route("/", get(|| async { Html("<p>  Hello  </p>") }));
+///
+/// // Minifier inside compression: minified, then compressed
+/// let app = router().layer(HtmlMinifierLayer::new()).layer(middleware::map_response(compress));
+/// assert_eq!(body_of(app).await, "gz:<p>Hello");
+///
+/// // Compression inside the minifier: the compressed body passes through as it was
+/// let app = router().layer(middleware::map_response(compress)).layer(HtmlMinifierLayer::new());
+/// assert_eq!(body_of(app).await, "gz:<p>  Hello  </p>");
+/// # });
+/// ```
+///
+/// Static pages served by `tower_http`'s `ServeDir` or `ServeFile` are minified like any
+/// other response, with their `Content-Length` rewritten. Those services answer `Range`
+/// requests from the file as stored, so minified responses withdraw their `Accept-Ranges`
+/// offer, and the `206` answers to ranges requested anyway are forwarded untouched:
+///
+/// ```
+/// use axum::{
+///     body::Body,
+///     http::{header, Request, StatusCode},
+///     Router,
+/// };
+/// use axum_mini::HtmlMinifierLayer;
+/// use http_body_util::BodyExt;
+/// use tower::Service;
+/// use tower_http::services::ServeDir;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let dir = std::env::temp_dir().join(format!("axum_mini-serve-dir-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("index.html"), "<ul>\n  <li>  One  </li>\n</ul>\n").unwrap();
+/// let mut app = Router::new().fallback_service(ServeDir::new(&dir)).layer(HtmlMinifierLayer::new());
+///
+/// let request = Request::builder().uri("/index.html").body(Body::empty()).unwrap();
+/// let response = app.call(request).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::OK);
+/// assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+/// assert_eq!(response.headers()[header::CONTENT_LENGTH], "16");
+/// assert_eq!(response.headers()[header::ACCEPT_RANGES], "none");
+/// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<ul><li>One</ul>");
+///
+/// let request = Request::builder()
+///     .uri("/index.html")
+///     .header(header::RANGE, "bytes=0-3")
+///     .body(Body::empty())
+///     .unwrap();
+/// let response = app.call(request).await.unwrap();
+/// assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+/// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<ul>");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// # });
+/// ```
+///
+/// Like any `tower` layer, it also wraps services outside a router, whatever their request
+/// body type:
+///
+/// ```
+/// use std::convert::Infallible;
+///
+/// use axum::{body::Body, http::{Request, Response}};
+/// use axum_mini::HtmlMinifierLayer;
+/// use http_body_util::BodyExt;
+/// use tower::{service_fn, Layer, Service};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// // Echoes `String` request bodies back as HTML
+/// let echo = service_fn(|req: Request<String>| async move {
+///     let response = Response::builder().header("content-type", "text/html").body(Body::from(req.into_body()));
+///     Ok::<_, Infallible>(response.unwrap())
+/// });
+/// let mut service = HtmlMinifierLayer::new().layer(echo);
+/// let response = service.call(Request::new("<p>  Hello  </p>".to_owned())).await.unwrap();
+/// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<p>Hello");
+/// # });
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HtmlMinifierLayer {
+    config: Arc<MinifierConfig>,
+}
+
+impl HtmlMinifierLayer {
+    /// Creates a layer using the default [`MinifierConfig`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a layer using the given [`MinifierConfig`].
+    pub fn with_config(config: MinifierConfig) -> Self {
+        Self { config: Arc::new(config) }
+    }
+}
+
+impl<S> Layer<S> for HtmlMinifierLayer {
+    type Service = HtmlMinifierService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HtmlMinifierService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Service produced by [`HtmlMinifierLayer`].
+#[derive(Clone, Debug)]
+pub struct HtmlMinifierService<S> {
+    inner: S,
+    config: Arc<MinifierConfig>,
+}
+
+/// Only the request head is read, so requests may have any body type; a layer further out
+/// may have changed it from [`Body`]. Responses must have a [`Body`].
+impl<S, B> Service<Request<B>> for HtmlMinifierService<S>
+where
+    S: Service<Request<B>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service that was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = self.config.clone();
+
+        Box::pin(async move {
+            let config = overrides::effective_config(&config, &req);
+            let info = RequestInfo::new(&config, &req);
+            let response = inner.call(req).await?;
+            let response = match process_response(&config, &info, response).await {
+                Ok(response) => response,
+                Err(err) => err.into_response(),
+            };
+            Ok(response)
+        })
+    }
+}
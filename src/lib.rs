@@ -1,59 +1,96 @@
 //! axum_mini – Lightweight HTML minifier middleware for Axum.
 //!
 //! See the [README](https://crates.io/crates/axum_mini) for full usage and examples.
+//!
+//! The middleware lives behind the default `axum` feature. Without it, the crate still
+//! provides [`minify_html_bytes`], [`MinifierConfig`] and the streaming bodies, with no
+//! dependency on axum or an async runtime, e.g. for WASM or a build script:
+//!
+//! ```
+//! use axum_mini::{minify_html_bytes, MinifierConfig};
+//!
+//! // No runtime is running here
+//! let minified = minify_html_bytes(b"<ul>\n  <li>  One  </li>\n</ul>", &MinifierConfig::default());
+//! assert_eq!(minified, b"<ul><li>One</ul>");
+//! ```
 
-use axum::{
-    body::{Body, Bytes},
-    http::{Request, Response, StatusCode},
-    middleware::Next,
-    response::IntoResponse,
-};
-use minify_html::{minify, Cfg};
-use http_body_util::BodyExt;
-
-/// Middleware that minifies HTML responses.
-pub async fn html_minifier(req: Request<Body>, next: Next) -> Result<impl IntoResponse, (StatusCode, String)> {
-    // Buffer entire response body
-    let (parts, body) = next.run(req).await.into_parts();
-    let response_bytes = response_buffer(body)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR,  e))?;
-
-    // Check content-type header
-    let is_html = parts.headers
-        .get("content-type")
-        .and_then(|v| v.to_str().ok())
-        .map(|ct| ct.contains("text/html"))
-        .unwrap_or(false);
-
-    // Minify if HTML
-    let final_body = if is_html {
-        let mut cfg = Cfg::new();
-        cfg.allow_removing_spaces_between_attributes = true;
-        cfg.minify_css = true;
-        cfg.minify_js = true;
-        cfg.remove_bangs = true;
-        cfg.remove_processing_instructions = true;
-        cfg.keep_comments = false;
-
-        Bytes::from(minify(&response_bytes, &cfg))
-    } else {
-        response_bytes
-    };
-
-    let response = Response::from_parts(parts, Body::from(final_body));
-    Ok(response)
-}
+#[cfg(feature = "axum")]
+mod assets;
+mod attrs;
+#[cfg(feature = "axum")]
+mod body;
+#[cfg(feature = "axum")]
+mod budget;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "encoding")]
+mod charset;
+mod comments;
+#[cfg(feature = "compress")]
+mod compress;
+mod config;
+mod content_type;
+mod engine;
+mod error;
+mod externalize;
+#[cfg(feature = "axum")]
+mod headers;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "axum")]
+mod layer;
+#[cfg(feature = "axum")]
+mod middleware;
+mod minify;
+#[cfg(feature = "multipart")]
+mod multipart;
+#[cfg(feature = "axum")]
+mod overrides;
+#[cfg(feature = "axum")]
+mod preload;
+mod protect;
+#[cfg(feature = "axum")]
+mod response_rewriter;
+#[cfg(feature = "lol_html")]
+mod rewriter;
+#[cfg(feature = "axum")]
+mod sample;
+mod scratch;
+mod stats;
+mod stream;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "axum")]
+mod verify;
+#[cfg(feature = "axum")]
+mod xml;
 
-/// Helper to read the entire body to bytes
-async fn response_buffer<B>(body: B) -> Result<axum::body::Bytes, String>
-where
-    B: axum::body::HttpBody<Data = axum::body::Bytes>,
-    B::Error: std::fmt::Display,
-{
-    let bytes = match body.collect().await {
-        Ok(collected) => collected.to_bytes(),
-        Err(err) => {return Err(format!("failed to read response body: {err}"));}
-    };
-    Ok(bytes)
-}
+#[cfg(feature = "axum")]
+pub use budget::CpuBudget;
+#[cfg(feature = "cache")]
+pub use cache::MinifyCache;
+pub use config::{
+    ExternalizeStylesFn, MinifierConfig, MinifierConfigBuilder, MinifyFn, MinifyMode, OnSkipFn, PostProcessFn,
+    PreProcessFn, SkipFn,
+};
+pub use content_type::ContentTypeClass;
+pub use error::{ConfigError, MinifyError};
+pub use minify_html::Cfg;
+#[cfg(feature = "axum")]
+pub use layer::{HtmlMinifierLayer, HtmlMinifierService};
+#[cfg(feature = "axum")]
+pub use middleware::{
+    html_minifier, html_minifier_with, html_minifier_with_state, html_request_minifier, html_request_minifier_with,
+    into_axum_request, minify_outcome, minify_response, minify_stream, MinifyOutcome,
+};
+pub use minify::{minify_html_bytes, minify_in_place};
+#[cfg(feature = "axum")]
+pub use overrides::MinifierOverride;
+#[cfg(feature = "axum")]
+pub use response_rewriter::{ResponseRewriter, RewriteLayer, RewriteService};
+#[cfg(feature = "lol_html")]
+pub use rewriter::LolHtmlMinifier;
+pub use stats::{MinifyStats, MinifyStatsCollector, SkipReason};
+pub use stream::StreamingMinifier;
+#[cfg(feature = "testing")]
+pub use testing::assert_minifies_to;
@@ -0,0 +1,1161 @@
+//! axum middleware running responses, and request bodies, through the minifier.
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{FromRef, State},
+    http::{
+        header::{
+            CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH, LINK,
+            TRANSFER_ENCODING, USER_AGENT,
+        },
+        response::Parts,
+        HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode, Uri,
+    },
+    middleware::Next,
+    response::IntoResponse,
+};
+use bytes::{Buf, BytesMut};
+use futures_core::TryStream;
+use http_body::Frame;
+use http_body_util::{BodyExt, Empty, LengthLimitError, Limited};
+
+#[cfg(feature = "compress")]
+use axum::http::header::CONTENT_ENCODING;
+#[cfg(feature = "encoding")]
+use crate::charset;
+#[cfg(feature = "compress")]
+use crate::compress;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "lol_html")]
+use crate::LolHtmlMinifier;
+#[cfg(feature = "multipart")]
+use crate::multipart;
+use crate::{
+    assets, body::ReplayBody, content_type, headers, minify, minify_html_bytes, overrides, preload,
+    protect::find_ignore_case, sample, verify, xml, ContentTypeClass, MinifierConfig, MinifyError, MinifyMode,
+    MinifyStats, OnSkipFn, SkipReason, StreamingMinifier,
+};
+
+/// Middleware that minifies HTML responses.
+///
+/// Like every function run by [`middleware::from_fn`](axum::middleware::from_fn), it takes a
+/// `Request<Body>`, since that is all [`Next`] can run. Request bodies of another type, e.g.
+/// from a `tower` layer further out, are converted to [`Body`] by axum's router already; to
+/// wrap other services, use [`HtmlMinifierLayer`](crate::HtmlMinifierLayer), which accepts any
+/// request body, or convert requests with [`into_axum_request`].
+pub async fn html_minifier(req: Request<Body>, next: Next) -> Result<impl IntoResponse, MinifyError> {
+    minify_with(&MinifierConfig::default(), req, next).await
+}
+
+/// Middleware that minifies HTML responses using a custom [`MinifierConfig`].
+///
+/// Apply it with `middleware::from_fn_with_state(config, html_minifier_with)`.
+pub async fn html_minifier_with(
+    State(cfg): State<MinifierConfig>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, MinifyError> {
+    minify_with(&cfg, req, next).await
+}
+
+/// Middleware that minifies HTML responses using the [`MinifierConfig`] in the router's state.
+///
+/// The config is pulled from any state `S` it can be extracted from with [`FromRef`], so it
+/// can live alongside the rest of the application state.
+///
+/// ```
+/// use axum::{extract::FromRef, middleware, routing::get, Router};
+/// use axum_mini::{html_minifier_with_state, MinifierConfig};
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     minifier: MinifierConfig,
+///     app_name: String,
+/// }
+///
+/// impl FromRef<AppState> for MinifierConfig {
+///     fn from_ref(state: &AppState) -> Self {
+///         state.minifier.clone()
+///     }
+/// }
+///
+/// let state = AppState {
+///     minifier: MinifierConfig::builder().keep_comments(true).build(),
+///     app_name: "demo".into(),
+/// };
+/// let app: Router = Router::new()
+///     .route("/", get(|| async { "Hello" }))
+///     .layer(middleware::from_fn_with_state(state.clone(), html_minifier_with_state::<AppState>))
+///     .with_state(state);
+/// ```
+pub async fn html_minifier_with_state<S>(
+    State(state): State<S>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<impl IntoResponse, MinifyError>
+where
+    MinifierConfig: FromRef<S>,
+{
+    let cfg = MinifierConfig::from_ref(&state);
+    drop(state);
+    minify_with(&cfg, req, next).await
+}
+
+/// Converts a request with any body into one that [`Next`] can run, by boxing its body.
+///
+/// ```
+/// use axum::http::Request;
+/// use axum_mini::into_axum_request;
+/// use http_body_util::{BodyExt, Full};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let req = Request::builder().uri("/page").body(Full::from("<p>Hello</p>")).unwrap();
+/// let req = into_axum_request(req);
+/// assert_eq!(req.uri(), "/page");
+/// assert_eq!(req.into_body().collect().await.unwrap().to_bytes(), "<p>Hello</p>");
+/// # });
+/// ```
+pub fn into_axum_request<B>(req: Request<B>) -> Request<Body>
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<axum::BoxError>,
+{
+    req.map(Body::new)
+}
+
+/// Middleware that minifies HTML request bodies before the handler sees them, e.g. for an
+/// endpoint previewing submitted markup.
+///
+/// Request bodies are held to the same checks as responses: their `Content-Type`, a
+/// `Content-Encoding` other than `identity`, [`MinifierConfig::max_body_bytes`],
+/// [`MinifierConfig::min_size_bytes`], the charset and so on.
+/// [`MinifierConfig::skip_if`] is called with a response's parts carrying the request's
+/// headers.
+///
+/// ```
+/// use axum::{body::Body, http::{header, Method, Request}, middleware, routing::post, Router};
+/// use axum_mini::html_request_minifier;
+/// use http_body_util::BodyExt;
+/// use tower::Service;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut app = Router::new()
+///     .route("/preview", post(|html: String| async move { html }))
+///     .layer(middleware::from_fn(html_request_minifier));
+///
+/// let request = Request::builder()
+///     .method(Method::POST)
+///     .uri("/preview")
+///     .header(header::CONTENT_TYPE, "text/html")
+///     .body(Body::from("<p>  Hello,   world!  </p>"))
+///     .unwrap();
+/// let response = app.call(request).await.unwrap();
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "<p>Hello, world!");
+/// # });
+/// ```
+pub async fn html_request_minifier(req: Request<Body>, next: Next) -> Result<Response<Body>, MinifyError> {
+    request_minifier(&MinifierConfig::default(), req, next).await
+}
+
+/// Middleware that minifies HTML request bodies using a custom [`MinifierConfig`].
+///
+/// Apply it with `middleware::from_fn_with_state(config, html_request_minifier_with)`.
+pub async fn html_request_minifier_with(
+    State(cfg): State<MinifierConfig>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response<Body>, MinifyError> {
+    request_minifier(&cfg, req, next).await
+}
+
+/// Minifies the request body according to `cfg`, then runs the inner service.
+async fn request_minifier(cfg: &MinifierConfig, req: Request<Body>, next: Next) -> Result<Response<Body>, MinifyError> {
+    match minify_request(cfg, req).await {
+        Ok(req) => Ok(next.run(req).await),
+        Err(err @ MinifyError::BodyRead(_)) => read_error_response(cfg, err),
+        Err(err) => Err(err),
+    }
+}
+
+/// Buffers `req` and minifies its body if it is HTML (or another enabled content type).
+async fn minify_request(cfg: &MinifierConfig, req: Request<Body>) -> Result<Request<Body>, MinifyError> {
+    let info = RequestInfo::new(cfg, &req);
+    let (mut parts, body) = req.into_parts();
+    if !cfg.is_enabled() || cfg.is_shutting_down() || headers::is_encoded(&parts.headers) {
+        return Ok(Request::from_parts(parts, body));
+    }
+    let Some(class) = content_type::classify(cfg, &parts.headers) else {
+        return Ok(Request::from_parts(parts, body));
+    };
+    let on_skip = cfg.on_skip.as_ref();
+    let cfg = cfg.per_content_type.get(&class).unwrap_or(cfg);
+
+    let bytes = match response_buffer(body, cfg.max_body_bytes, cfg.reject_too_large).await? {
+        Buffered::Complete(bytes) => bytes,
+        Buffered::TooLarge(body) | Buffered::Trailers(body) => return Ok(Request::from_parts(parts, body)),
+    };
+    if bytes.is_empty() {
+        return Ok(Request::from_parts(parts, Body::from(bytes)));
+    }
+    // `skip_if` sees the request's headers as those of a response
+    let skip_if = |body: &[u8]| {
+        cfg.skip_if.as_ref().is_some_and(|skip_if| {
+            let (mut response, ()) = Response::new(()).into_parts();
+            response.headers = parts.headers.clone();
+            skip_if(&response, body)
+        })
+    };
+    let Some(decoded) = decode_checked(cfg, &info, class, &parts.headers, &bytes, on_skip, skip_if) else {
+        return Ok(Request::from_parts(parts, Body::from(bytes)));
+    };
+
+    // As with responses, a failed minification leaves the body as it was
+    let minified = match minify_body(cfg, class, &parts.headers, decoded.input).await {
+        #[cfg(feature = "encoding")]
+        Ok(minified) => match decoded.encoding {
+            Some(encoding) => charset::encode(encoding, &minified).map_or(bytes, Bytes::from),
+            None => minified,
+        },
+        #[cfg(not(feature = "encoding"))]
+        Ok(minified) => minified,
+        Err(_) => bytes,
+    };
+    parts.headers.remove(TRANSFER_ENCODING);
+    if parts.headers.contains_key(CONTENT_LENGTH) {
+        parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(minified.len()));
+    }
+    Ok(Request::from_parts(parts, Body::from(minified)))
+}
+
+/// Minifies a response exactly as the middleware would for a `GET /` request.
+///
+/// Handy for checking that templates minify as expected without building a router.
+///
+/// ```
+/// use axum::{body::Body, http::{header, Response}};
+/// use axum_mini::{minify_response, MinifierConfig};
+/// use http_body_util::BodyExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let response = Response::builder()
+///     .header(header::CONTENT_TYPE, "text/html")
+///     .body(Body::from("<p>  Hello,   world!  </p>"))
+///     .unwrap();
+/// let response = minify_response(response, &MinifierConfig::default()).await;
+/// let body = response.into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "<p>Hello, world!");
+/// # });
+/// ```
+pub async fn minify_response(response: Response<Body>, cfg: &MinifierConfig) -> Response<Body> {
+    let info = RequestInfo::new(cfg, &Request::new(()));
+    process_response(cfg, &info, response)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+/// Buffers the HTML body produced by `stream` and minifies it as [`minify_response`] would,
+/// for handlers building bodies with [`Body::from_stream`] rather than going through the
+/// middleware.
+///
+/// Fails with [`MinifyError::BodyRead`] if the stream yields an error, even when
+/// [`MinifierConfig::read_error_message`] or [`MinifierConfig::read_error_status`] is set,
+/// since there is no response here to answer with. Fails with [`MinifyError::TooLarge`] if it
+/// is too large and [`MinifierConfig::reject_too_large`] is set.
+///
+/// ```
+/// use axum::{body::Bytes, http::{header, Response}};
+/// use axum_mini::{minify_stream, MinifierConfig};
+/// use futures_util::stream;
+/// use http_body_util::BodyExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let chunks = ["<ul>\n  <li>  One", "  </li>\n  <li>Two", "</li>\n</ul>\n"];
+/// let stream = stream::iter(chunks.map(|chunk| Ok::<_, std::io::Error>(Bytes::from(chunk))));
+///
+/// let body = minify_stream(stream, &MinifierConfig::default()).await.unwrap();
+/// let response = Response::builder().header(header::CONTENT_TYPE, "text/html").body(body).unwrap();
+/// assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "<ul><li>One<li>Two</ul>");
+/// # });
+/// ```
+pub async fn minify_stream<S>(stream: S, cfg: &MinifierConfig) -> Result<Body, MinifyError>
+where
+    S: TryStream + Send + 'static,
+    S::Ok: Into<Bytes>,
+    S::Error: Into<axum::BoxError>,
+{
+    let mut response = Response::new(Body::from_stream(stream));
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+    let info = RequestInfo::new(cfg, &Request::new(()));
+    Ok(minify_and_record(cfg, &info, response).await?.into_body())
+}
+
+/// Runs the inner service and minifies its response according to `cfg`.
+async fn minify_with(cfg: &MinifierConfig, req: Request<Body>, next: Next) -> Result<Response<Body>, MinifyError> {
+    Ok(minify_outcome(cfg, req, next).await?.response)
+}
+
+/// A response that went through the minifier, with what the minifier did to it.
+#[derive(Debug)]
+pub struct MinifyOutcome {
+    /// The response to send.
+    pub response: Response<Body>,
+    /// The body's stats if it was of a minifiable type and buffered; check
+    /// [`MinifyStats::minified`] for whether the minifier actually ran. `None` for responses
+    /// forwarded without looking at their body.
+    pub stats: Option<MinifyStats>,
+}
+
+/// Runs the inner service and minifies its response according to `cfg`, reporting what was
+/// done, for middleware wrapping the minifier.
+///
+/// ```
+/// use axum::{
+///     body::Body,
+///     http::Request,
+///     middleware::{self, Next},
+///     response::{Html, Response},
+///     routing::get,
+///     Router,
+/// };
+/// use axum_mini::{minify_outcome, MinifierConfig, MinifyError};
+/// use tower::Service;
+///
+/// async fn report_savings(req: Request<Body>, next: Next) -> Result<Response, MinifyError> {
+///     let outcome = minify_outcome(&MinifierConfig::default(), req, next).await?;
+///     let mut response = outcome.response;
+///     let report = match outcome.stats {
+///         Some(stats) if stats.minified => format!("saved {}", stats.saved_bytes()),
+///         Some(_) => "skipped".to_owned(),
+///         None => "untouched".to_owned(),
+///     };
+///     response.headers_mut().insert("x-minify-report", report.parse().unwrap());
+///     Ok(response)
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut app = Router::new()
+///     .route("/page", get(|| async { Html("<p>  Hello  </p>") }))
+///     .route("/text", get(|| async { "plain  text" }))
+///     .layer(middleware::from_fn(report_savings));
+///
+/// let mut report = async |uri: &str, minify: &str| {
+///     let request = Request::builder().uri(uri).header("x-minify", minify).body(Body::empty()).unwrap();
+///     let response = app.call(request).await.unwrap();
+///     response.headers()["x-minify-report"].to_str().unwrap().to_owned()
+/// };
+/// assert_eq!(report("/page", "on").await, "saved 8");
+/// // The client opted out, so the body was looked at but left alone
+/// assert_eq!(report("/page", "off").await, "skipped");
+/// assert_eq!(report("/text", "on").await, "untouched");
+/// # });
+/// ```
+pub async fn minify_outcome(cfg: &MinifierConfig, req: Request<Body>, next: Next) -> Result<MinifyOutcome, MinifyError> {
+    // Read everything needed from the request before handing it on
+    let cfg = overrides::effective_config(cfg, &req);
+    let info = RequestInfo::new(&cfg, &req);
+    let response = next.run(req).await;
+    let response = process_response(&cfg, &info, response).await?;
+    let stats = response.extensions().get::<MinifyStats>().copied();
+    Ok(MinifyOutcome { response, stats })
+}
+
+/// Debug headers reporting the body length before and after minification.
+const X_ORIGINAL_BYTES: HeaderName = HeaderName::from_static("x-original-bytes");
+const X_MINIFIED_BYTES: HeaderName = HeaderName::from_static("x-minified-bytes");
+
+/// Request details that the minifier needs after the request has been consumed.
+pub(crate) struct RequestInfo {
+    method: Method,
+    uri: Uri,
+    /// The client opted out with the opt-out header or query parameter, or its `User-Agent` is
+    /// bypassed.
+    opted_out: bool,
+    /// Coding to compress minified bodies with, if the client accepts one.
+    #[cfg(feature = "compress")]
+    encoding: Option<compress::Encoding>,
+    /// The `If-None-Match` header, kept only when ETags are recomputed.
+    if_none_match: Option<HeaderValue>,
+}
+
+impl RequestInfo {
+    pub(crate) fn new<B>(cfg: &MinifierConfig, req: &Request<B>) -> Self {
+        Self {
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+            opted_out: cfg.opt_out_header.as_ref().is_some_and(|name| is_opt_out(req.headers(), name))
+                || cfg.opt_out_query.as_ref().is_some_and(|param| is_query_opt_out(req.uri(), param))
+                || is_bypassed_agent(req.headers(), &cfg.bypass_user_agents),
+            #[cfg(feature = "compress")]
+            encoding: cfg.compress.then(|| compress::Encoding::negotiate(req.headers())).flatten(),
+            if_none_match: cfg.recompute_etag.then(|| req.headers().get(IF_NONE_MATCH).cloned()).flatten(),
+        }
+    }
+}
+
+/// Whether `headers` carry the opt-out header set to `off` or `false`.
+fn is_opt_out(headers: &HeaderMap, name: &HeaderName) -> bool {
+    headers
+        .get(name)
+        .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"off") || v.as_bytes().eq_ignore_ascii_case(b"false"))
+}
+
+/// Whether the query string sets `param` to `0`, `off` or `false`.
+fn is_query_opt_out(uri: &Uri, param: &str) -> bool {
+    uri.query()
+        .into_iter()
+        .flat_map(|query| query.split('&'))
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(name, value)| name == param && ["0", "off", "false"].iter().any(|off| value.eq_ignore_ascii_case(off)))
+}
+
+/// Whether the `User-Agent` header contains one of `agents`, ignoring ASCII case.
+fn is_bypassed_agent(headers: &HeaderMap, agents: &[String]) -> bool {
+    if agents.is_empty() {
+        return false;
+    }
+    let Some(user_agent) = headers.get(USER_AGENT) else { return false };
+    let user_agent = user_agent.as_bytes();
+    agents.iter().any(|agent| find_ignore_case(user_agent, agent.as_bytes(), 0).is_some())
+}
+
+/// Buffers `response` and minifies its body if it is HTML (or another enabled content type).
+pub(crate) async fn process_response(
+    cfg: &MinifierConfig,
+    info: &RequestInfo,
+    response: Response<Body>,
+) -> Result<Response<Body>, MinifyError> {
+    match minify_and_record(cfg, info, response).await {
+        Err(err @ MinifyError::BodyRead(_)) => read_error_response(cfg, err),
+        result => result,
+    }
+}
+
+/// Like [`process_response`], but returning read errors instead of answering them.
+async fn minify_and_record(
+    cfg: &MinifierConfig,
+    info: &RequestInfo,
+    response: Response<Body>,
+) -> Result<Response<Body>, MinifyError> {
+    let result = minify_or_forward(cfg, info, response).await;
+    if let Some(collector) = &cfg.stats_collector {
+        collector.record(result.as_ref().ok().and_then(|response| response.extensions().get::<MinifyStats>()));
+    }
+    result
+}
+
+async fn minify_or_forward(
+    cfg: &MinifierConfig,
+    info: &RequestInfo,
+    response: Response<Body>,
+) -> Result<Response<Body>, MinifyError> {
+    let (mut parts, body) = response.into_parts();
+
+    // Honor the handler's opt-out, without leaking the header to the client
+    if let Some(name) = &cfg.opt_out_header {
+        let opted_out = is_opt_out(&parts.headers, name);
+        parts.headers.remove(name);
+        if opted_out {
+            return Ok(Response::from_parts(parts, body));
+        }
+    }
+
+    // Like the opt-out, the fragment marker is only meant for the minifier
+    let marked_fragment = cfg.fragment_header.as_ref().is_some_and(|name| parts.headers.remove(name).is_some());
+
+    if !cfg.is_enabled() || cfg.is_shutting_down() {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    if !sample::sampled(cfg.sample_rate) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Bodies of e.g. HEAD responses are never sent, so don't spend any work on them
+    if !cfg.methods.contains(&info.method) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    let path = info.uri.path();
+    if cfg.skip_path_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // HTML files offered for download should arrive as stored, even without a disposition
+    if cfg.download_path_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str())) && is_html_file(path) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Rewriting part of a body would no longer match the range it claims to be
+    if parts.status == StatusCode::PARTIAL_CONTENT || parts.headers.contains_key(CONTENT_RANGE) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // These never carry a body, so there is nothing to do and no length to rewrite
+    if parts.status == StatusCode::NO_CONTENT || parts.status == StatusCode::NOT_MODIFIED {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Error pages are rarely worth it and are easier to debug when left as-is
+    if parts.status.as_u16() >= 400 && !cfg.minify_error_responses {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Compressed bodies are opaque bytes to the minifier, and `no-transform` forbids touching them
+    if headers::is_encoded(&parts.headers) || headers::is_no_transform(&parts.headers) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Framing headers that contradict each other are how requests get smuggled; reshaping the
+    // body would hide that from whatever sits downstream
+    if parts.headers.contains_key(TRANSFER_ENCODING) && parts.headers.contains_key(CONTENT_LENGTH) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(method = %info.method, path = %info.uri.path(), "response has both Transfer-Encoding and Content-Length, forwarding unchanged");
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Downloads should arrive exactly as the handler produced them
+    if headers::is_attachment(&parts.headers) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Event streams and upgraded connections may never end, so buffering them would hang
+    let is_upgrade = parts.status == StatusCode::SWITCHING_PROTOCOLS || headers::is_upgrade(&parts.headers);
+    if is_upgrade || content_type::is_event_stream(&parts.headers) {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Streaming mode rewrites HTML as it passes through instead of buffering it
+    if cfg.mode != MinifyMode::Buffered && content_type::classify(cfg, &parts.headers) == Some(ContentTypeClass::Html) {
+        if client_opted_out(cfg, info, &mut parts.headers) {
+            return Ok(Response::from_parts(parts, body));
+        }
+        parts.headers.remove(CONTENT_LENGTH);
+        headers::weaken_etag(&mut parts.headers);
+        headers::refuse_ranges(&mut parts.headers);
+        let body = match cfg.mode {
+            #[cfg(feature = "lol_html")]
+            MinifyMode::LolHtml => Body::new(LolHtmlMinifier::with_config(body, cfg)),
+            _ => Body::new(StreamingMinifier::with_config(body, cfg)),
+        };
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Only bodies that may be minified are worth buffering; the rest stream through as they
+    // are, however large
+    let minifiable = content_type::classify(cfg, &parts.headers).is_some();
+    let sniffable = cfg.sniff_html && !parts.headers.contains_key(CONTENT_TYPE);
+    if !minifiable && !sniffable {
+        return Ok(Response::from_parts(parts, body));
+    }
+
+    // Buffer entire response body, unless it is too large to hold in memory. Only bodies
+    // that would have been minified are worth failing the response over.
+    let response_bytes = match response_buffer(body, cfg.max_body_bytes, cfg.reject_too_large && minifiable).await? {
+        Buffered::Complete(bytes) => bytes,
+        Buffered::TooLarge(body) => {
+            if minifiable {
+                report_skip(cfg.on_skip.as_ref(), SkipReason::TooLarge);
+            }
+            return Ok(Response::from_parts(parts, body));
+        }
+        Buffered::Trailers(body) => return Ok(Response::from_parts(parts, body)),
+    };
+    // A body that disagrees with its own length is broken upstream; don't reshape it further
+    if cfg.strict_content_length && declared_length(&parts.headers).is_some_and(|len| len != response_bytes.len() as u64) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(method = %info.method, path = %info.uri.path(), actual_len = response_bytes.len(), "body does not match Content-Length, forwarding unchanged");
+        if minifiable {
+            report_skip(cfg.on_skip.as_ref(), SkipReason::LengthMismatch);
+        }
+        return Ok(Response::from_parts(parts, Body::from(response_bytes)));
+    }
+
+    if response_bytes.is_empty() {
+        return Ok(Response::from_parts(parts, Body::empty()));
+    }
+    // The body is now a single known-length chunk, so leave framing to the server
+    parts.headers.remove(TRANSFER_ENCODING);
+
+    // Check content-type header, or failing that, what the body looks like
+    let sniffed = || (sniffable && content_type::looks_like_html(&response_bytes)).then_some(ContentTypeClass::Html);
+    let Some(class) = content_type::classify(cfg, &parts.headers).or_else(sniffed) else {
+        return Ok(Response::from_parts(parts, Body::from(response_bytes)));
+    };
+    // From here on, the settings for this kind of document apply, but failures are reported to
+    // the callback configured at the top
+    let on_skip = cfg.on_skip.as_ref();
+    let cfg = cfg.per_content_type.get(&class).unwrap_or(cfg);
+
+    if client_opted_out(cfg, info, &mut parts.headers) {
+        return Ok(unminified(parts, response_bytes));
+    }
+
+    let skip_if = |body: &[u8]| cfg.skip_if.as_ref().is_some_and(|skip_if| skip_if(&parts, body));
+    let Some(decoded) = decode_checked(cfg, info, class, &parts.headers, &response_bytes, on_skip, skip_if) else {
+        return Ok(unminified(parts, response_bytes));
+    };
+
+    let body = Pending {
+        class,
+        original: response_bytes,
+        input: decoded.input,
+        #[cfg(feature = "encoding")]
+        encoding: decoded.encoding,
+        marked_fragment,
+        on_skip,
+    };
+    apply_pipeline(cfg, info, parts, body).await
+}
+
+/// A buffered body as UTF-8 text, ready to minify.
+struct Decoded {
+    input: Bytes,
+    /// The charset to convert the minified body back to.
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static encoding_rs::Encoding>,
+}
+
+/// Runs the checks that buffered request and response bodies share, with `skip_if` standing
+/// in for [`MinifierConfig::skip_if`], and converts `bytes` to UTF-8. Returns `None` for
+/// bodies to send as they are.
+fn decode_checked(
+    cfg: &MinifierConfig,
+    info: &RequestInfo,
+    class: ContentTypeClass,
+    headers: &HeaderMap,
+    bytes: &Bytes,
+    on_skip: Option<&OnSkipFn>,
+    skip_if: impl FnOnce(&[u8]) -> bool,
+) -> Option<Decoded> {
+    #[cfg(not(feature = "tracing"))]
+    let _ = info;
+    // Forward too-small bodies unchanged
+    if bytes.len() < cfg.min_size_bytes || skip_if(bytes) {
+        return None;
+    }
+
+    // Bodies in legacy charsets are minified as UTF-8 and converted back afterwards
+    #[cfg(feature = "encoding")]
+    let Ok(encoding) = charset::declared(headers) else {
+        report_skip(on_skip, SkipReason::Charset);
+        return None;
+    };
+    #[cfg(feature = "encoding")]
+    let input = match encoding {
+        Some(encoding) => match charset::decode(encoding, bytes) {
+            Some(utf8) => Bytes::from(utf8),
+            None => {
+                report_skip(on_skip, SkipReason::Charset);
+                return None;
+            }
+        },
+        None => bytes.clone(),
+    };
+    #[cfg(not(feature = "encoding"))]
+    let input = bytes.clone();
+    #[cfg(not(feature = "encoding"))]
+    let _ = headers;
+
+    // A body that isn't text is almost certainly mislabeled, and minifying it would mangle it.
+    // Multipart bodies may mix in binary parts; only their HTML parts are checked.
+    if !is_multipart(class) && std::str::from_utf8(&input).is_err() {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(method = %info.method, path = %info.uri.path(), content_type = ?class, "body is not valid UTF-8, forwarding unchanged");
+        report_skip(on_skip, SkipReason::InvalidUtf8);
+        return None;
+    }
+
+    if cfg.skip_minified_below.is_some_and(|threshold| looks_minified(&input, threshold)) {
+        return None;
+    }
+    Some(Decoded {
+        input,
+        #[cfg(feature = "encoding")]
+        encoding,
+    })
+}
+
+/// A buffered body that passed every check and is about to be minified.
+struct Pending<'a> {
+    class: ContentTypeClass,
+    /// The body as the handler produced it, sent instead if a stage fails.
+    original: Bytes,
+    /// The body as UTF-8.
+    input: Bytes,
+    /// The charset to convert the minified body back to.
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static encoding_rs::Encoding>,
+    marked_fragment: bool,
+    on_skip: Option<&'a OnSkipFn>,
+}
+
+/// Runs a body through the pipeline's stages, always in this order:
+///
+/// 1. transform, with [`MinifierConfig::pre_process`];
+/// 2. minify, from the cache, with the cheap fragment pass or with the full minifier, then
+///    convert back to the body's charset;
+/// 3. post-process, with [`MinifierConfig::post_process`];
+/// 4. headers: stats, debug headers, preload `Link`s and the static `Cache-Control`;
+/// 5. compress;
+/// 6. framing: `Content-Length`, `ETag` and `Accept-Ranges` are made to describe the body as
+///    sent.
+///
+/// So the minifier sees what the transform added, the post-process step sees minified output,
+/// headers describe the post-processed body and compression gets the final bytes. Dry runs
+/// stop once the stats are recorded and send the original body with its headers as they were.
+async fn apply_pipeline(
+    cfg: &MinifierConfig,
+    info: &RequestInfo,
+    mut parts: Parts,
+    body: Pending<'_>,
+) -> Result<Response<Body>, MinifyError> {
+    let Pending {
+        class,
+        original,
+        input,
+        #[cfg(feature = "encoding")]
+        encoding,
+        marked_fragment,
+        on_skip,
+    } = body;
+
+    // 1. Transform. Runs in this same buffered pass, so whatever it adds is minified along
+    // with the rest
+    let input = match &cfg.pre_process {
+        Some(pre_process) if class == ContentTypeClass::Html => pre_process(input),
+        _ => input,
+    };
+
+    // 2. Minify
+    let input_len = input.len();
+    #[cfg(feature = "cache")]
+    let settings = cfg.cache.as_ref().map(|_| cfg.output_fingerprint()).unwrap_or_default();
+    #[cfg(feature = "cache")]
+    let cached = cfg.cache.as_ref().and_then(|cache| cache.get(class, settings, &input));
+    #[cfg(not(feature = "cache"))]
+    let cached = None;
+
+    // Small fragments aren't worth the full pass, nor the trip to the blocking pool
+    let is_fragment = class == ContentTypeClass::Html
+        && input.len() <= cfg.fragment_max_bytes
+        && (marked_fragment || content_type::is_fragment_type(cfg, &parts.headers));
+
+    let final_body = match cached {
+        Some(minified) => minified,
+        None if is_fragment => Bytes::from(minify::trim_fragment(&input, cfg)),
+        None => match minify_body(cfg, class, &parts.headers, input.clone()).await {
+            Ok(minified) => {
+                #[cfg(feature = "cache")]
+                if let Some(cache) = &cfg.cache {
+                    cache.insert(class, settings, input, minified.clone());
+                }
+                minified
+            }
+            Err(MinifyError::ShuttingDown) => return Ok(unminified(parts, original)),
+            // Minification is only an optimization; a panic or timeout must not turn the response
+            // into an error
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                if matches!(_err, MinifyError::Diverged) {
+                    tracing::error!(method = %info.method, path = %info.uri.path(), "minified body lost visible text, forwarding original body");
+                } else {
+                    tracing::warn!(method = %info.method, path = %info.uri.path(), error = %_err, "minification failed, forwarding original body");
+                }
+                if let Some(reason) = skip_reason(&_err) {
+                    report_skip(on_skip, reason);
+                }
+                return Ok(unminified(parts, original));
+            }
+        },
+    };
+
+    // Too little was saved to be worth whatever the minifier changed
+    if cfg.min_savings_ratio < 1.0 && final_body.len() as f64 > cfg.min_savings_ratio * input_len as f64 {
+        return Ok(unminified(parts, original));
+    }
+
+    #[cfg(feature = "encoding")]
+    let final_body = match encoding {
+        Some(encoding) => match charset::encode(encoding, &final_body) {
+            Some(encoded) => Bytes::from(encoded),
+            None => {
+                report_skip(on_skip, SkipReason::Charset);
+                return Ok(unminified(parts, original));
+            }
+        },
+        None => final_body,
+    };
+
+    // 3. Post-process
+    let final_body = match &cfg.post_process {
+        Some(post_process) if !cfg.dry_run => post_process(final_body, &mut parts.headers),
+        _ => final_body,
+    };
+
+    // 4. Headers
+    let stats = MinifyStats {
+        original_len: original.len(),
+        final_len: final_body.len(),
+        minified: true,
+    };
+    parts.extensions.insert(stats);
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        method = %info.method,
+        path = %info.uri.path(),
+        content_type = ?class,
+        original_len = stats.original_len,
+        minified_len = stats.final_len,
+        reduction_pct = stats.reduction_pct(),
+        dry_run = cfg.dry_run,
+        "minified response",
+    );
+
+    #[cfg(feature = "metrics")]
+    {
+        let label = class.label();
+        metrics::counter!("axum_mini_original_bytes_total", "content_type" => label).increment(stats.original_len as u64);
+        metrics::counter!("axum_mini_minified_bytes_total", "content_type" => label).increment(stats.final_len as u64);
+    }
+
+    // Only measure the savings; the client gets exactly what the handler produced
+    if cfg.dry_run {
+        return Ok(Response::from_parts(parts, Body::from(original)));
+    }
+
+    if cfg.debug_headers && class == ContentTypeClass::Html {
+        parts.headers.insert(X_ORIGINAL_BYTES, HeaderValue::from(stats.original_len));
+        parts.headers.insert(X_MINIFIED_BYTES, HeaderValue::from(stats.final_len));
+    }
+
+    // The whole page is in memory anyway, so finding what it loads is cheap
+    if cfg.preload_links > 0 && class == ContentTypeClass::Html {
+        for link in preload::links(&final_body, cfg.preload_links) {
+            parts.headers.append(LINK, link);
+        }
+    }
+
+    // Static pages can be cached for long, and saying so here spares a separate header layer
+    if let Some(cache_control) = &cfg.static_cache_control {
+        let path = info.uri.path();
+        let is_static = cfg.static_path_prefixes.iter().any(|prefix| path.starts_with(prefix.as_str()));
+        if is_static && !parts.headers.contains_key(CACHE_CONTROL) {
+            parts.headers.insert(CACHE_CONTROL, cache_control.clone());
+        }
+    }
+
+    // 5. Compress. Doing it here saves a separate compression layer from buffering the body
+    // again
+    #[cfg(feature = "compress")]
+    let final_body = if cfg.compress {
+        headers::append_vary(&mut parts.headers, &axum::http::header::ACCEPT_ENCODING);
+        match info.encoding {
+            Some(encoding) => compress_body(encoding, final_body, &mut parts.headers).await,
+            None => final_body,
+        }
+    } else {
+        final_body
+    };
+
+    // 6. Framing. The original Content-Length no longer matches the body; inserting replaces
+    // every value sent with it. Headers describing the body are only ever replaced by name, so
+    // repeated headers such as `Set-Cookie` keep every value
+    if cfg.defer_content_length {
+        parts.headers.remove(CONTENT_LENGTH);
+    } else {
+        parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(final_body.len()));
+    }
+    headers::refuse_ranges(&mut parts.headers);
+    if cfg.recompute_etag && parts.headers.contains_key(ETAG) {
+        let etag = headers::weak_etag(&final_body);
+        // The client's copy is still current, so there is no need to send the body at all
+        let conditional = matches!(info.method, Method::GET | Method::HEAD) && parts.status == StatusCode::OK;
+        if conditional && info.if_none_match.as_ref().is_some_and(|tags| headers::etag_matches(tags, &etag)) {
+            parts.status = StatusCode::NOT_MODIFIED;
+            parts.headers.insert(ETAG, etag);
+            parts.headers.remove(CONTENT_LENGTH);
+            return Ok(Response::from_parts(parts, Body::empty()));
+        }
+        parts.headers.insert(ETAG, etag);
+    }
+
+    Ok(Response::from_parts(parts, Body::from(final_body)))
+}
+
+/// The response configured to be sent instead of `err` when a body couldn't be read, or
+/// `err` itself if there is none.
+fn read_error_response(cfg: &MinifierConfig, err: MinifyError) -> Result<Response<Body>, MinifyError> {
+    if cfg.read_error_status.is_none() && cfg.read_error_message.is_none() {
+        return Err(err);
+    }
+    let status = cfg.read_error_status.unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let message = cfg.read_error_message.clone().unwrap_or_else(|| err.to_string());
+    Ok((status, message).into_response())
+}
+
+/// Builds a response for a minifiable body that is sent unchanged.
+fn unminified(mut parts: Parts, body: Bytes) -> Response<Body> {
+    parts.extensions.insert(MinifyStats {
+        original_len: body.len(),
+        final_len: body.len(),
+        minified: false,
+    });
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Tells the `on_skip` callback, if any, why a body is sent unminified.
+fn report_skip(on_skip: Option<&OnSkipFn>, reason: SkipReason) {
+    if let Some(on_skip) = on_skip {
+        on_skip(reason);
+    }
+}
+
+/// The reason to report for a minification that failed with `err`.
+fn skip_reason(err: &MinifyError) -> Option<SkipReason> {
+    match err {
+        MinifyError::MinifyPanic => Some(SkipReason::Panic),
+        MinifyError::Timeout => Some(SkipReason::Timeout),
+        MinifyError::Busy => Some(SkipReason::Busy),
+        MinifyError::OverBudget => Some(SkipReason::OverBudget),
+        MinifyError::Diverged => Some(SkipReason::Diverged),
+        MinifyError::BodyRead(_) | MinifyError::TooLarge | MinifyError::ShuttingDown => None,
+    }
+}
+
+/// Parses the `Content-Length` header, if present and valid.
+fn declared_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Whether `path` names a file with an `.html` or `.htm` extension.
+fn is_html_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+}
+
+/// Whether the client asked for an unminified response.
+///
+/// Clients may opt out with a header, so a minifiable body depends on that request header;
+/// this notes it in `Vary`, as it does `User-Agent` when some are bypassed. The query is part
+/// of the URL, so it needs no such note.
+fn client_opted_out(cfg: &MinifierConfig, info: &RequestInfo, headers: &mut HeaderMap) -> bool {
+    if let Some(name) = &cfg.opt_out_header {
+        headers::append_vary(headers, name);
+    }
+    if !cfg.bypass_user_agents.is_empty() {
+        headers::append_vary(headers, &USER_AGENT);
+    }
+    info.opted_out
+}
+
+/// Whether `body` seems minified already: collapsible whitespace (newlines and runs of
+/// whitespace) makes up less than `threshold` of its bytes.
+fn looks_minified(body: &[u8], threshold: f64) -> bool {
+    let mut previous_space = false;
+    let collapsible = body
+        .iter()
+        .filter(|&&byte| {
+            let space = byte.is_ascii_whitespace();
+            let collapsible = space && (previous_space || byte == b'\n');
+            previous_space = space;
+            collapsible
+        })
+        .count();
+    (collapsible as f64) < threshold * body.len() as f64
+}
+
+/// Minifies `input` as a document of the given class.
+async fn minify_body(
+    cfg: &MinifierConfig,
+    class: ContentTypeClass,
+    headers: &HeaderMap,
+    input: Bytes,
+) -> Result<Bytes, MinifyError> {
+    // Minification is CPU-bound (tens of milliseconds for a ~1MB document), so run it on
+    // the blocking pool to keep the async worker free for other requests
+    let timeout = cfg.timeout;
+    // The flag may have been set while the body was being read; work already running finishes
+    if cfg.is_shutting_down() {
+        return Err(MinifyError::ShuttingDown);
+    }
+    if cfg.cpu_budget.as_ref().is_some_and(|budget| !budget.has_budget()) {
+        return Err(MinifyError::OverBudget);
+    }
+    // The permit moves into the task, so a timed-out minification keeps holding it until it
+    // actually finishes
+    let permit = match &cfg.max_concurrent {
+        Some(semaphore) => Some(semaphore.clone().try_acquire_owned().map_err(|_| MinifyError::Busy)?),
+        None => None,
+    };
+    let cfg = cfg.clone();
+    #[cfg(feature = "multipart")]
+    let boundary = multipart::boundary(headers).unwrap_or_default();
+    #[cfg(not(feature = "multipart"))]
+    let _ = headers;
+    #[cfg(feature = "metrics")]
+    let started = std::time::Instant::now();
+    #[cfg(feature = "tracing")]
+    let span = tracing::Span::current();
+    let task = tokio::task::spawn_blocking(move || {
+        // Keep anything a custom minifier logs within the request's span
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let _permit = permit;
+        let started = std::time::Instant::now();
+        let minified = match class {
+            ContentTypeClass::Html => minify_html_bytes(&input, &cfg),
+            ContentTypeClass::Css => assets::minify_css(&input, &cfg.to_cfg()),
+            ContentTypeClass::Js => assets::minify_js(&input, &cfg.to_cfg()),
+            ContentTypeClass::Xml => xml::minify(&input),
+            #[cfg(feature = "json")]
+            ContentTypeClass::Json => {
+                let json = json::minify_html_fields(&input, &cfg.json_html_pointers, &cfg);
+                if cfg.minify_json { json::minify(&json) } else { json }
+            }
+            #[cfg(feature = "multipart")]
+            ContentTypeClass::Multipart => multipart::minify(&input, &boundary, &cfg),
+        };
+        if let Some(budget) = &cfg.cpu_budget {
+            budget.spend(started.elapsed());
+        }
+        let verify = class == ContentTypeClass::Html && sample::sampled(cfg.verify_rate);
+        if verify && !verify::same_text(&input, &minified) {
+            return Err(MinifyError::Diverged);
+        }
+        // Share the original buffer rather than keep an identical copy alive
+        Ok(if minified == input { input } else { Bytes::from(minified) })
+    });
+    // A blocking task can't be interrupted; on timeout it runs on, but its result is dropped
+    let joined = match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, task).await.map_err(|_| MinifyError::Timeout)?,
+        None => task.await,
+    };
+    let minified = joined.map_err(|_| MinifyError::MinifyPanic)??;
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("axum_mini_minify_duration_seconds", "content_type" => class.label())
+        .record(started.elapsed().as_secs_f64());
+    Ok(minified)
+}
+
+/// Whether `class` is [`ContentTypeClass::Multipart`].
+fn is_multipart(_class: ContentTypeClass) -> bool {
+    #[cfg(feature = "multipart")]
+    return _class == ContentTypeClass::Multipart;
+    #[cfg(not(feature = "multipart"))]
+    false
+}
+
+/// Compresses a minified body, updating the headers that describe it.
+#[cfg(feature = "compress")]
+async fn compress_body(encoding: compress::Encoding, body: Bytes, headers: &mut HeaderMap) -> Bytes {
+    let input = body.clone();
+    match tokio::task::spawn_blocking(move || encoding.compress(&input)).await {
+        Ok(compressed) => {
+            headers.insert(CONTENT_ENCODING, encoding.header_value());
+            // A strong ETag must differ between codings of the same content
+            headers::weaken_etag(headers);
+            Bytes::from(compressed)
+        }
+        Err(_) => body,
+    }
+}
+
+/// Result of buffering a response body.
+pub(crate) enum Buffered {
+    /// The whole body, read into memory.
+    Complete(Bytes),
+    /// The body exceeded the size limit; this replays what was read followed by the remainder.
+    TooLarge(Body),
+    /// The body ended in trailers, which a rewritten body couldn't carry; this replays it
+    /// whole, trailers included.
+    Trailers(Body),
+}
+
+/// Helper to read the entire body to bytes, giving up once it exceeds `limit`
+///
+/// A body sent as one frame is kept as-is; otherwise frames are appended to a single buffer
+/// sized from the body's size hint, so the bytes are copied only once. Frames may use any
+/// [`Buf`] type; `Bytes` frames are taken over without copying.
+pub(crate) async fn response_buffer<B>(body: B, limit: Option<usize>, reject_too_large: bool) -> Result<Buffered, MinifyError>
+where
+    B: HttpBody + Unpin + Send + 'static,
+    B::Data: Send,
+    B::Error: std::fmt::Display + Into<axum::BoxError>,
+{
+    let mut body = body.map_frame(|frame| frame.map_data(|mut data| data.copy_to_bytes(data.remaining())));
+    if let Some(limit) = limit {
+        // Nothing is forwarded when rejecting, so `Limited` can simply cut collection off
+        if reject_too_large {
+            return match Limited::new(body, limit).collect().await {
+                Ok(collected) => match collected.trailers().cloned() {
+                    Some(trailers) => Ok(replay_with_trailers(collected.to_bytes(), trailers, Empty::new())),
+                    None => Ok(Buffered::Complete(collected.to_bytes())),
+                },
+                Err(err) if err.is::<LengthLimitError>() => Err(MinifyError::TooLarge),
+                Err(err) => Err(MinifyError::BodyRead(err.to_string())),
+            };
+        }
+        // Skip buffering altogether when the body says up front that it is too large
+        if body.size_hint().lower() > limit as u64 {
+            return Ok(Buffered::TooLarge(Body::new(body)));
+        }
+    }
+
+    let mut first: Option<Bytes> = None;
+    let mut buf = BytesMut::new();
+    let mut frames = 0_usize;
+    let mut len = 0;
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|err| MinifyError::BodyRead(err.to_string()))?;
+        let data = match frame.into_data().map_err(Frame::into_trailers) {
+            Ok(data) => data,
+            Err(Ok(trailers)) => {
+                let read = first.take().unwrap_or_else(|| buf.split().freeze());
+                return Ok(replay_with_trailers(read, trailers, body));
+            }
+            Err(Err(_)) => continue,
+        };
+        frames += 1;
+        len += data.len();
+        if first.is_none() && buf.is_empty() {
+            first = Some(data);
+        } else {
+            if let Some(first) = first.take() {
+                let remaining = usize::try_from(body.size_hint().lower()).unwrap_or(usize::MAX);
+                let expected = len.saturating_add(remaining);
+                buf.reserve(limit.map_or(expected, |limit| expected.min(limit.saturating_add(1))));
+                buf.extend_from_slice(&first);
+            }
+            buf.extend_from_slice(&data);
+        }
+        if limit.is_some_and(|limit| len > limit) {
+            let read = first.take().unwrap_or_else(|| buf.split().freeze());
+            return Ok(Buffered::TooLarge(Body::new(ReplayBody::new(vec![Frame::data(read)], body))));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(frames, len, "buffered response body");
+    #[cfg(not(feature = "tracing"))]
+    let _ = frames;
+
+    Ok(Buffered::Complete(first.unwrap_or_else(|| buf.freeze())))
+}
+
+/// Forwards a body that ended in `trailers` after `read`, with whatever `rest` still holds.
+fn replay_with_trailers<B>(read: Bytes, trailers: HeaderMap, rest: B) -> Buffered
+where
+    B: HttpBody<Data = Bytes> + Unpin + Send + 'static,
+    B::Error: Into<axum::BoxError>,
+{
+    let mut frames = Vec::with_capacity(2);
+    if !read.is_empty() {
+        frames.push(Frame::data(read));
+    }
+    frames.push(Frame::trailers(trailers));
+    Buffered::Trailers(Body::new(ReplayBody::new(frames, rest)))
+}
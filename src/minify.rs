@@ -0,0 +1,187 @@
+//! Minification of HTML documents, independent of axum and of any async runtime.
+
+use crate::{
+    attrs, comments, content_type, engine, externalize,
+    protect::{self, find, Stash},
+    scratch,
+    stream::WhitespaceCollapser,
+    MinifierConfig,
+};
+
+/// Elements whose content is kept verbatim with `preserve_sensitive_whitespace`.
+const WHITESPACE_SENSITIVE_TAGS: [&str; 3] = ["pre", "textarea", "code"];
+
+/// HTML's boolean attributes, kept as written unless `collapse_boolean_attributes` is set.
+const BOOLEAN_ATTRIBUTES: [&str; 25] = [
+    "allowfullscreen",
+    "async",
+    "autofocus",
+    "autoplay",
+    "checked",
+    "controls",
+    "default",
+    "defer",
+    "disabled",
+    "formnovalidate",
+    "hidden",
+    "inert",
+    "ismap",
+    "itemscope",
+    "loop",
+    "multiple",
+    "muted",
+    "nomodule",
+    "novalidate",
+    "open",
+    "playsinline",
+    "readonly",
+    "required",
+    "reversed",
+    "selected",
+];
+
+/// Collapses the whitespace of a small fragment and trims it, leaving everything else, and
+/// the regions `cfg` preserves, as is.
+#[cfg(feature = "axum")]
+pub(crate) fn trim_fragment(input: &[u8], cfg: &MinifierConfig) -> Vec<u8> {
+    let collapsed = WhitespaceCollapser::for_config(cfg).collapse(input);
+    collapsed.trim_ascii().to_vec()
+}
+
+/// Minifies an HTML document exactly as the middleware would, keeping the regions `cfg` asks
+/// to preserve intact.
+///
+/// This is independent of axum, e.g. for minifying pages in a build script.
+///
+/// ```
+/// use axum_mini::{minify_html_bytes, MinifierConfig};
+///
+/// let minified = minify_html_bytes(b"<p>  Hello,   world!  </p>", &MinifierConfig::default());
+/// assert_eq!(minified, b"<p>Hello, world!");
+/// ```
+pub fn minify_html_bytes(input: &[u8], cfg: &MinifierConfig) -> Vec<u8> {
+    let mut minified = if cfg.keep_important_comments && find(input, b"/*!", 0).is_some() {
+        let important = comments::in_scripts(input);
+        comments::restore(&minify_document(input, cfg), &important).unwrap_or_else(|| input.to_vec())
+    } else {
+        minify_document(input, cfg)
+    };
+    if cfg.preserve_trailing_newline && input.ends_with(b"\n") {
+        while matches!(minified.last(), Some(b'\n' | b'\r')) {
+            minified.pop();
+        }
+        minified.push(b'\n');
+    }
+    minified
+}
+
+/// Minifies the HTML document in `html`, replacing it with the result of [`minify_html_bytes`].
+///
+/// For servers minifying in a loop: the old buffer is kept for this thread's next call, whose
+/// attribute passes, such as [`MinifierConfig::collapse_class_whitespace`], then write into it
+/// instead of allocating. Every pass starts from a cleared buffer, so calls never see each
+/// other's output. The reuse covers those passes only: `minify_html` itself still allocates a
+/// fresh output buffer on every call.
+///
+/// ```
+/// use axum_mini::{minify_in_place, MinifierConfig};
+///
+/// let cfg = MinifierConfig::aggressive();
+/// let mut html = br#"<p class="  lead  intro ">  A much longer first paragraph  </p>"#.to_vec();
+/// minify_in_place(&mut html, &cfg);
+/// assert_eq!(html, br#"<p class="lead intro">A much longer first paragraph"#);
+///
+/// // A shorter document reuses the buffer without picking up any of the previous one
+/// let mut html = br#"<b class=" x ">Hi</b>"#.to_vec();
+/// minify_in_place(&mut html, &cfg);
+/// assert_eq!(html, b"<b class=x>Hi</b>");
+/// ```
+pub fn minify_in_place(html: &mut Vec<u8>, cfg: &MinifierConfig) {
+    let minified = minify_html_bytes(html, cfg);
+    scratch::recycle(std::mem::replace(html, minified));
+}
+
+fn minify_document(input: &[u8], cfg: &MinifierConfig) -> Vec<u8> {
+    // Collapsing leaves tags and comments alone, and so attributes such as nonces; the
+    // elements and marked regions the config preserves are copied verbatim
+    if cfg.full_minify_max_bytes.is_some_and(|max| input.len() > max) {
+        return WhitespaceCollapser::for_config(cfg).collapse(input);
+    }
+    let mut minify_cfg = cfg.to_cfg();
+    if cfg.fragment_safe && !content_type::looks_like_html(input) {
+        engine::keep_closing_tags(&mut minify_cfg);
+    }
+    if cfg.amp_mode {
+        engine::amp_safe(&mut minify_cfg);
+    }
+    let protect_comments = !engine::keeps_comments(&minify_cfg) && !cfg.preserve_comment_prefixes.is_empty();
+    let protect = protect_comments
+        || cfg.preserve_sensitive_whitespace
+        || !cfg.preserve_whitespace_tags.is_empty()
+        || !cfg.minify_noscript
+        || cfg.amp_mode
+        || cfg.honor_no_minify_markers
+        || cfg.preserve_nonces
+        || cfg.preserve_data_uris
+        || !cfg.collapse_boolean_attributes;
+    if !protect {
+        return rewrite_minified(cfg.run_minifier(input, &minify_cfg), cfg);
+    }
+    // Leave documents that already contain our placeholder text alone rather than corrupt them
+    if !Stash::can_protect(input) {
+        return input.to_vec();
+    }
+
+    let mut stash = Stash::default();
+    let mut html = input.to_vec();
+    if cfg.honor_no_minify_markers {
+        html = protect::marked_regions(&html, &mut stash);
+    }
+    let mut verbatim_tags: Vec<&str> = cfg.preserve_whitespace_tags.iter().map(String::as_str).collect();
+    if cfg.preserve_sensitive_whitespace {
+        verbatim_tags.extend(WHITESPACE_SENSITIVE_TAGS);
+    }
+    if !cfg.minify_noscript {
+        verbatim_tags.push("noscript");
+    }
+    if !verbatim_tags.is_empty() {
+        html = protect::element_contents(&html, &verbatim_tags, &mut stash);
+    }
+    if cfg.amp_mode {
+        html = protect::amp_boilerplate(&html, &mut stash);
+    }
+    if protect_comments {
+        html = protect::comments(&html, &cfg.preserve_comment_prefixes, &mut stash);
+    }
+    if cfg.preserve_nonces {
+        // Not even an empty nonce may be dropped
+        html = protect::attributes_named(&html, &["nonce"], |_| true, &mut stash);
+    }
+    if cfg.preserve_data_uris {
+        html = protect::data_uris(&html, &mut stash);
+    }
+    if !cfg.collapse_boolean_attributes {
+        // Only attributes written with a value would be collapsed
+        html = protect::attributes_named(&html, &BOOLEAN_ATTRIBUTES, |value| value.is_some(), &mut stash);
+    }
+    let minified = rewrite_minified(cfg.run_minifier(&html, &minify_cfg), cfg);
+    stash.restore(&minified).unwrap_or_else(|| input.to_vec())
+}
+
+/// Applies the optional passes that tighten the minifier's output further.
+fn rewrite_minified(mut html: Vec<u8>, cfg: &MinifierConfig) -> Vec<u8> {
+    let passes: [(bool, scratch::Pass); 5] = [
+        (cfg.collapse_class_whitespace, attrs::collapse_class_whitespace),
+        (cfg.minify_inline_styles, attrs::minify_inline_styles),
+        (cfg.remove_redundant_type_attrs, attrs::remove_redundant_type_attrs),
+        (cfg.minify_inline_event_handlers, attrs::minify_event_handlers),
+        // Last, so that the passes above see the values as the minifier wrote them
+        (!cfg.remove_attribute_quotes, attrs::quote_attribute_values),
+    ];
+    scratch::run_passes(&mut html, passes.into_iter().filter_map(|(on, pass)| on.then_some(pass)));
+    // AMP pages may not link stylesheets
+    match &cfg.externalize_styles {
+        Some(store) if !cfg.amp_mode => externalize::styles(&html, cfg.externalize_styles_min_bytes, store),
+        _ => html,
+    }
+}
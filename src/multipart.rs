@@ -0,0 +1,95 @@
+//! Minification of the HTML parts of `multipart/*` responses.
+
+use axum::http::HeaderMap;
+use mime::Mime;
+
+use crate::{content_type::content_type, minify_html_bytes, protect::find, MinifierConfig};
+
+/// The boundary parameter of a `multipart/*` response, if it has one.
+pub(crate) fn boundary(headers: &HeaderMap) -> Option<String> {
+    let content_type = content_type(headers)?;
+    if content_type.type_() != mime::MULTIPART {
+        return None;
+    }
+    Some(content_type.get_param(mime::BOUNDARY)?.as_str().to_owned())
+}
+
+/// Minifies the parts whose `Content-Type` is one of [`MinifierConfig::content_types`].
+///
+/// Everything else, including the boundaries, the preamble, the epilogue and the other parts,
+/// is copied byte for byte. A malformed body is returned unchanged.
+pub(crate) fn minify(input: &[u8], boundary: &str, cfg: &MinifierConfig) -> Vec<u8> {
+    minify_parts(input, boundary, cfg).unwrap_or_else(|| input.to_vec())
+}
+
+fn minify_parts(input: &[u8], boundary: &str, cfg: &MinifierConfig) -> Option<Vec<u8>> {
+    // Every delimiter but a leading one is preceded by a line break that belongs to it
+    let delimiter = [b"\r\n--", boundary.as_bytes()].concat();
+    let mut at = if input.starts_with(&delimiter[2..]) {
+        0
+    } else {
+        find(input, &delimiter, 0)? + 2
+    };
+    let mut out = Vec::with_capacity(input.len());
+    out.extend_from_slice(&input[..at]);
+    loop {
+        let after = at + delimiter.len() - 2;
+        // The close delimiter; what follows is the epilogue
+        if input[after..].starts_with(b"--") {
+            out.extend_from_slice(&input[at..]);
+            return Some(out);
+        }
+        let line_end = find(input, b"\r\n", after)?;
+        let next = find(input, &delimiter, line_end)?;
+        let part_start = (line_end + 2).min(next);
+        out.extend_from_slice(&input[at..part_start]);
+        out.extend_from_slice(&minify_part(&input[part_start..next], cfg));
+        out.extend_from_slice(b"\r\n");
+        at = next + 2;
+    }
+}
+
+/// Minifies one part, headers included, if it is HTML sent as plain text.
+fn minify_part(part: &[u8], cfg: &MinifierConfig) -> Vec<u8> {
+    let (head, body) = match part.strip_prefix(b"\r\n") {
+        Some(body) => (&part[..0], body),
+        None => match find(part, b"\r\n\r\n", 0) {
+            Some(end) => (&part[..end], &part[end + 4..]),
+            None => return part.to_vec(),
+        },
+    };
+    let Ok(head) = std::str::from_utf8(head) else {
+        return part.to_vec();
+    };
+    let headers: Vec<(&str, &str)> = head
+        .split("\r\n")
+        .filter(|line| !line.is_empty())
+        .map(|line| line.split_once(':').map_or((line, ""), |(name, value)| (name, value.trim())))
+        .collect();
+    let header = |name: &str| headers.iter().find(|(n, _)| n.trim().eq_ignore_ascii_case(name)).map(|(_, v)| *v);
+
+    let is_html = header("content-type")
+        .and_then(|value| value.parse::<Mime>().ok())
+        .is_some_and(|ty| cfg.content_types.iter().any(|allowed| ty.essence_str().eq_ignore_ascii_case(allowed)));
+    // Base64 or quoted-printable content would have to be decoded first
+    let is_plain = header("content-transfer-encoding")
+        .is_none_or(|encoding| ["7bit", "8bit", "binary"].iter().any(|plain| encoding.eq_ignore_ascii_case(plain)));
+    if !is_html || !is_plain || std::str::from_utf8(body).is_err() {
+        return part.to_vec();
+    }
+
+    let minified = minify_html_bytes(body, cfg);
+    let mut out = Vec::with_capacity(part.len());
+    for line in head.split("\r\n").filter(|line| !line.is_empty()) {
+        match line.split_once(':') {
+            Some((name, _)) if name.trim().eq_ignore_ascii_case("content-length") => {
+                out.extend_from_slice(format!("{name}: {}", minified.len()).as_bytes());
+            }
+            _ => out.extend_from_slice(line.as_bytes()),
+        }
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(b"\r\n");
+    out.extend_from_slice(&minified);
+    out
+}
@@ -0,0 +1,102 @@
+//! Per-request changes to the middleware's configuration.
+
+use std::{
+    borrow::Cow,
+    sync::{atomic::AtomicBool, Arc},
+};
+
+use axum::http::Request;
+
+use crate::MinifierConfig;
+
+/// Settings to change for one request, read from the request's extensions.
+///
+/// Insert it before the minifier runs, e.g. from an outer middleware, and every field that is
+/// `Some` replaces the configured value for that request. Overrides apply to the
+/// [`per_content_type`](MinifierConfig::per_content_type) settings too.
+///
+/// ```
+/// use axum::{
+///     body::Body,
+///     http::Request,
+///     middleware::{self, Next},
+///     response::Html,
+///     routing::get,
+///     Router,
+/// };
+/// use axum_mini::{HtmlMinifierLayer, MinifierOverride};
+/// use http_body_util::BodyExt;
+/// use tower::Service;
+///
+/// async fn keep_widget_js(mut req: Request<Body>, next: Next) -> axum::response::Response {
+///     if req.uri().path() == "/widget" {
+///         req.extensions_mut().insert(MinifierOverride { minify_js: Some(false), ..Default::default() });
+///     }
+///     next.run(req).await
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let page = || async { Html("<script>let  answer = 42 ;</script>") };
+/// let mut app = Router::new()
+///     .route("/", get(page))
+///     .route("/widget", get(page))
+///     .layer(HtmlMinifierLayer::new())
+///     .layer(middleware::from_fn(keep_widget_js));
+///
+/// let request = Request::builder().uri("/widget").body(Body::empty()).unwrap();
+/// let body = app.call(request).await.unwrap().into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "<script>let  answer = 42 ;</script>");
+///
+/// let body = app.call(Request::new(Body::empty())).await.unwrap().into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "<script>let answer=42</script>");
+/// # });
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinifierOverride {
+    /// Turns minification on or off, whatever [`MinifierConfig::enabled`] says.
+    pub enabled: Option<bool>,
+    /// Replaces [`MinifierConfig::dry_run`].
+    pub dry_run: Option<bool>,
+    /// Replaces [`MinifierConfig::minify_css`].
+    pub minify_css: Option<bool>,
+    /// Replaces [`MinifierConfig::minify_js`].
+    pub minify_js: Option<bool>,
+    /// Replaces [`MinifierConfig::keep_comments`].
+    pub keep_comments: Option<bool>,
+    /// Replaces [`MinifierConfig::debug_headers`].
+    pub debug_headers: Option<bool>,
+}
+
+impl MinifierOverride {
+    /// Applies the override to `cfg`.
+    fn apply_to(&self, cfg: &mut MinifierConfig) {
+        let set = |field: &mut bool, value: Option<bool>| {
+            if let Some(value) = value {
+                *field = value;
+            }
+        };
+        set(&mut cfg.dry_run, self.dry_run);
+        set(&mut cfg.minify_css, self.minify_css);
+        set(&mut cfg.minify_js, self.minify_js);
+        set(&mut cfg.keep_comments, self.keep_comments);
+        set(&mut cfg.debug_headers, self.debug_headers);
+        if let Some(enabled) = self.enabled {
+            cfg.enabled = Some(Arc::new(AtomicBool::new(enabled)));
+        }
+        for class_cfg in cfg.per_content_type.values_mut() {
+            self.apply_to(class_cfg);
+        }
+    }
+}
+
+/// The configuration for `req`: `cfg` with the request's [`MinifierOverride`], if any, applied.
+pub(crate) fn effective_config<'a, B>(cfg: &'a MinifierConfig, req: &Request<B>) -> Cow<'a, MinifierConfig> {
+    match req.extensions().get::<MinifierOverride>() {
+        Some(overrides) => {
+            let mut cfg = cfg.clone();
+            overrides.apply_to(&mut cfg);
+            Cow::Owned(cfg)
+        }
+        None => Cow::Borrowed(cfg),
+    }
+}
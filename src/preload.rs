@@ -0,0 +1,53 @@
+//! `Link` preload headers for the stylesheets and scripts a page loads.
+
+use axum::http::HeaderValue;
+
+use crate::{
+    attrs::{attributes, for_each_start_tag},
+    protect::opens_tag,
+};
+
+/// `Link` header values preloading the first `limit` stylesheets and scripts of `html`, in
+/// document order.
+///
+/// Module scripts get `rel=modulepreload`; `nomodule` scripts, which modern browsers skip,
+/// and URLs that can't be written in a header are left out.
+pub(crate) fn links(html: &[u8], limit: usize) -> Vec<HeaderValue> {
+    let mut links: Vec<HeaderValue> = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for_each_start_tag(html, |lt, end| {
+        if links.len() >= limit {
+            return;
+        }
+        let attrs = attributes(html, lt, end);
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|attr| html[attr.name.clone()].eq_ignore_ascii_case(name.as_bytes()))
+                .map(|attr| attr.value.clone().map_or("", |value| std::str::from_utf8(&html[value]).unwrap_or("")))
+        };
+        let link = if opens_tag(html, lt, "link") {
+            let is_stylesheet = attr("rel").is_some_and(|rel| {
+                let mut tokens = rel.split_ascii_whitespace();
+                tokens.clone().any(|token| token.eq_ignore_ascii_case("stylesheet"))
+                    && !tokens.any(|token| token.eq_ignore_ascii_case("alternate"))
+            });
+            attr("href").filter(|_| is_stylesheet).map(|href| (href, "rel=preload; as=style"))
+        } else if opens_tag(html, lt, "script") && attr("nomodule").is_none() {
+            let is_module = attr("type").is_some_and(|ty| ty.trim().eq_ignore_ascii_case("module"));
+            attr("src").map(|src| (src, if is_module { "rel=modulepreload" } else { "rel=preload; as=script" }))
+        } else {
+            None
+        };
+        let Some((url, params)) = link else { return };
+        let url = url.trim().replace("&amp;", "&");
+        if url.is_empty() || url.contains(['<', '>']) || seen.contains(&url) {
+            return;
+        }
+        if let Ok(value) = HeaderValue::try_from(format!("<{url}>; {params}")) {
+            links.push(value);
+            seen.push(url);
+        }
+    });
+    links
+}
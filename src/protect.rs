@@ -0,0 +1,304 @@
+//! Protection passes that shield parts of a document from the minifier.
+//!
+//! Protected regions are swapped for placeholder text before minification and spliced back
+//! verbatim afterwards.
+
+use crate::attrs::{attributes, for_each_start_tag};
+
+/// Marker wrapping every placeholder; a private-use character the minifier leaves alone.
+const MARKER: char = '\u{e000}';
+const PREFIX: &str = "\u{e000}axum_mini:";
+
+/// Comments marking the start and end of a region left exactly as written.
+pub(crate) const NO_MINIFY_START: &[u8] = b"<!--minify:off-->";
+pub(crate) const NO_MINIFY_END: &[u8] = b"<!--minify:on-->";
+
+/// Raw-text elements whose content must not be scanned for markup.
+const RAW_TEXT_TAGS: [&str; 2] = ["script", "style"];
+
+/// Regions taken out of a document, restored after minification.
+#[derive(Default)]
+pub(crate) struct Stash {
+    regions: Vec<Vec<u8>>,
+}
+
+impl Stash {
+    /// Whether `html` can be protected without its own content clashing with placeholders.
+    pub(crate) fn can_protect(html: &[u8]) -> bool {
+        find(html, PREFIX.as_bytes(), 0).is_none()
+    }
+
+    /// Stashes `region` and writes its placeholder to `out`.
+    pub(crate) fn push(&mut self, out: &mut Vec<u8>, region: &[u8]) {
+        out.extend_from_slice(placeholder(self.regions.len()).as_bytes());
+        self.regions.push(region.to_vec());
+    }
+
+    /// Puts every stashed region back into `minified`.
+    ///
+    /// Returns `None` if the minifier dropped or altered a placeholder.
+    pub(crate) fn restore(&self, minified: &[u8]) -> Option<Vec<u8>> {
+        let mut out = minified.to_vec();
+        // Later regions may contain placeholders of earlier ones, so restore newest first
+        for (index, region) in self.regions.iter().enumerate().rev() {
+            let placeholder = placeholder(index);
+            let at = find(&out, placeholder.as_bytes(), 0)?;
+            out.splice(at..at + placeholder.len(), region.iter().copied());
+        }
+        Some(out)
+    }
+}
+
+fn placeholder(index: usize) -> String {
+    format!("{PREFIX}{index}{MARKER}")
+}
+
+/// Stashes comments whose text starts with one of `prefixes`.
+pub(crate) fn comments(html: &[u8], prefixes: &[String], stash: &mut Stash) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    let mut i = 0;
+    while let Some(lt) = find(html, b"<", i) {
+        if html[lt..].starts_with(b"<!--") {
+            let Some(close) = find(html, b"-->", lt + 4) else { break };
+            let end = close + 3;
+            let text = &html[lt + 4..close];
+            if prefixes.iter().any(|prefix| text.starts_with(prefix.as_bytes())) {
+                out.extend_from_slice(&html[flushed..lt]);
+                stash.push(&mut out, &html[lt..end]);
+                flushed = end;
+            }
+            i = end;
+        } else if let Some(end) = raw_text_end(html, lt) {
+            i = end;
+        } else {
+            i = lt + 1;
+        }
+    }
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+/// Stashes every attribute named in `names` that `keep(value)` accepts, name and value together, so
+/// that the minifier neither drops, requotes nor shortens it.
+pub(crate) fn attributes_named(
+    html: &[u8],
+    names: &[&str],
+    keep: impl Fn(Option<&[u8]>) -> bool,
+    stash: &mut Stash,
+) -> Vec<u8> {
+    attributes_where(
+        html,
+        |name, value| names.iter().any(|wanted| name.eq_ignore_ascii_case(wanted.as_bytes())) && keep(value),
+        stash,
+    )
+}
+
+/// Stashes every attribute for which `keep(name, value)` holds, name and value together.
+fn attributes_where(html: &[u8], keep: impl Fn(&[u8], Option<&[u8]>) -> bool, stash: &mut Stash) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    for_each_start_tag(html, |lt, end| {
+        for attr in attributes(html, lt, end) {
+            let value = attr.value.clone().map(|value| &html[value]);
+            if keep(&html[attr.name.clone()], value) {
+                out.extend_from_slice(&html[flushed..attr.span.start]);
+                stash.push(&mut out, &html[attr.span.clone()]);
+                flushed = attr.span.end;
+            }
+        }
+    });
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+/// Stashes `data:` URIs: attributes whose value is one, and the argument of every
+/// `url(data:...)` in stylesheets and `style` attributes, quotes included.
+pub(crate) fn data_uris(html: &[u8], stash: &mut Stash) -> Vec<u8> {
+    let html = attributes_where(html, |_, value| value.is_some_and(is_data_uri), stash);
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    let mut i = 0;
+    while let Some(at) = find_ignore_case(&html, b"url(", i) {
+        let start = at + 4;
+        i = start;
+        let arg = html[start..].trim_ascii_start();
+        let uri = arg.strip_prefix(b"\"").or_else(|| arg.strip_prefix(b"'")).unwrap_or(arg);
+        if !is_data_uri(uri) {
+            continue;
+        }
+        let Some(end) = url_end(&html, start) else { break };
+        out.extend_from_slice(&html[flushed..start]);
+        stash.push(&mut out, &html[start..end]);
+        flushed = end;
+        i = end;
+    }
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+fn is_data_uri(value: &[u8]) -> bool {
+    value.trim_ascii_start().get(..5).is_some_and(|scheme| scheme.eq_ignore_ascii_case(b"data:"))
+}
+
+/// Returns the index of the `)` closing a `url(` whose argument starts at `from`, skipping
+/// over a quoted argument.
+fn url_end(html: &[u8], from: usize) -> Option<usize> {
+    let mut quote = None;
+    for (offset, &byte) in html.get(from..)?.iter().enumerate() {
+        match quote {
+            Some(q) if byte == q => quote = None,
+            Some(_) => {}
+            None if byte == b'"' || byte == b'\'' => quote = Some(byte),
+            None if byte == b')' => return Some(from + offset),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Stashes everything between `<!--minify:off-->` and `<!--minify:on-->` markers.
+///
+/// Markers nest, and a region that is never switched back on runs to the end of the
+/// document, so mistakes err on the side of not minifying.
+pub(crate) fn marked_regions(html: &[u8], stash: &mut Stash) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    while let Some(start) = find(html, NO_MINIFY_START, flushed) {
+        let content_start = start + NO_MINIFY_START.len();
+        let mut depth = 1;
+        let mut i = content_start;
+        let mut content_end = html.len();
+        while let Some(lt) = find(html, b"<!--minify:o", i) {
+            if html[lt..].starts_with(NO_MINIFY_START) {
+                depth += 1;
+            } else if html[lt..].starts_with(NO_MINIFY_END) {
+                depth -= 1;
+                if depth == 0 {
+                    content_end = lt;
+                    break;
+                }
+            }
+            i = lt + 1;
+        }
+        out.extend_from_slice(&html[flushed..content_start]);
+        stash.push(&mut out, &html[content_start..content_end]);
+        flushed = content_end;
+    }
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+/// Stashes the content of every element named in `tags`, leaving the tags themselves to be
+/// minified. Nested elements of the same name are kept within the outer one; elements that
+/// are never closed are left alone.
+pub(crate) fn element_contents(html: &[u8], tags: &[&str], stash: &mut Stash) -> Vec<u8> {
+    elements_where(html, tags, |_| true, stash)
+}
+
+/// Stashes the CSS of AMP's `<style amp-boilerplate>` elements, which must stay as written.
+pub(crate) fn amp_boilerplate(html: &[u8], stash: &mut Stash) -> Vec<u8> {
+    elements_where(html, &["style"], |start| find_ignore_case(start, b"amp-boilerplate", 0).is_some(), stash)
+}
+
+/// Stashes the content of every element named in `tags` whose start tag `keep(start)` accepts.
+fn elements_where(html: &[u8], tags: &[&str], keep: impl Fn(&[u8]) -> bool, stash: &mut Stash) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len());
+    let mut flushed = 0;
+    let mut i = 0;
+    while let Some(lt) = find(html, b"<", i) {
+        let start = || tag_end(html, lt).map(|end| &html[lt..end]);
+        if html[lt..].starts_with(b"<!--") {
+            i = find(html, b"-->", lt + 4).map_or(html.len(), |close| close + 3);
+        } else if let Some(tag) = tags.iter().find(|tag| opens_tag(html, lt, tag) && start().is_some_and(&keep)) {
+            let Some(content_start) = tag_end(html, lt) else { break };
+            let Some(content_end) = matching_close(html, tag, content_start) else { break };
+            if content_end > content_start {
+                out.extend_from_slice(&html[flushed..content_start]);
+                stash.push(&mut out, &html[content_start..content_end]);
+                flushed = content_end;
+            }
+            i = content_end;
+        } else if let Some(end) = raw_text_end(html, lt) {
+            i = end;
+        } else {
+            i = lt + 1;
+        }
+    }
+    out.extend_from_slice(&html[flushed..]);
+    out
+}
+
+/// Returns the index just past the `>` of the tag starting at `lt`, skipping quoted values.
+pub(crate) fn tag_end(html: &[u8], lt: usize) -> Option<usize> {
+    let mut quote = None;
+    for (offset, &byte) in html.get(lt..)?.iter().enumerate() {
+        match quote {
+            Some(q) if byte == q => quote = None,
+            Some(_) => {}
+            None if byte == b'"' || byte == b'\'' => quote = Some(byte),
+            None if byte == b'>' => return Some(lt + offset + 1),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Finds the `</tag` closing the element whose content starts at `from`.
+pub(crate) fn matching_close(html: &[u8], tag: &str, from: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = from;
+    while let Some(lt) = find(html, b"<", i) {
+        if closes_tag(html, lt, tag) {
+            if depth == 0 {
+                return Some(lt);
+            }
+            depth -= 1;
+        } else if opens_tag(html, lt, tag) {
+            depth += 1;
+        }
+        i = lt + 1;
+    }
+    None
+}
+
+/// If a raw-text element opens at `lt`, returns the index just past its closing tag.
+pub(crate) fn raw_text_end(html: &[u8], lt: usize) -> Option<usize> {
+    let tag = RAW_TEXT_TAGS.iter().find(|tag| opens_tag(html, lt, tag))?;
+    let closing = format!("</{tag}");
+    let end = find_ignore_case(html, closing.as_bytes(), lt + 1)
+        .and_then(|at| find(html, b">", at))
+        .map_or(html.len(), |gt| gt + 1);
+    Some(end)
+}
+
+/// Whether a `<tag` start tag (case-insensitive) begins at `lt`.
+pub(crate) fn opens_tag(html: &[u8], lt: usize, tag: &str) -> bool {
+    let name_end = lt + 1 + tag.len();
+    html.get(lt + 1..name_end)
+        .is_some_and(|name| name.eq_ignore_ascii_case(tag.as_bytes()))
+        && html
+            .get(name_end)
+            .is_none_or(|&b| b == b'>' || b == b'/' || b.is_ascii_whitespace())
+}
+
+/// Whether a `</tag` end tag (case-insensitive) begins at `lt`.
+pub(crate) fn closes_tag(html: &[u8], lt: usize, tag: &str) -> bool {
+    html.get(lt + 1) == Some(&b'/') && opens_tag(html, lt + 1, tag)
+}
+
+pub(crate) fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|at| at + from)
+}
+
+pub(crate) fn find_ignore_case(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+        .map(|at| at + from)
+}
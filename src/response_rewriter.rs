@@ -0,0 +1,231 @@
+//! A buffering layer driving any [`ResponseRewriter`], with the minifier as one of them.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{
+        header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, TRANSFER_ENCODING},
+        response::Parts,
+        HeaderValue, Request, Response, StatusCode,
+    },
+    response::IntoResponse,
+};
+use http_body_util::BodyExt;
+use tower::{Layer, Service};
+
+use crate::{
+    content_type, headers,
+    middleware::{response_buffer, Buffered},
+    minify_response, MinifierConfig,
+};
+
+/// Rewrites a buffered response body, along with the headers describing it.
+///
+/// Implement it to run custom transformations, such as injecting markup, through
+/// [`RewriteLayer`]. Implementations can be written as `async fn rewrite`. A pair of rewriters
+/// is a rewriter too, running the first and then the second on its output, so several
+/// rewriters share one buffering pass:
+///
+/// ```
+/// use axum::{
+///     body::{Body, Bytes},
+///     http::{header, response::Parts, Request},
+///     response::Html,
+///     routing::get,
+///     Router,
+/// };
+/// use axum_mini::{MinifierConfig, ResponseRewriter, RewriteLayer};
+/// use http_body_util::BodyExt;
+/// use tower::Service;
+///
+/// struct Banner;
+///
+/// impl ResponseRewriter for Banner {
+///     async fn rewrite(&self, parts: &mut Parts, body: Bytes) -> Bytes {
+///         if parts.headers[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html") {
+///             return [b"<!-- served by axum_mini -->".as_slice(), &body].concat().into();
+///         }
+///         body
+///     }
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut app = Router::new()
+///     .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+///     .route("/text", get(|| async { "plain  text" }))
+///     .layer(RewriteLayer::new((MinifierConfig::default(), Banner)));
+///
+/// let body = app.call(Request::new(Body::empty())).await.unwrap().into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "<!-- served by axum_mini --><p>Hello");
+///
+/// let request = Request::builder().uri("/text").body(Body::empty()).unwrap();
+/// let body = app.call(request).await.unwrap().into_body().collect().await.unwrap().to_bytes();
+/// assert_eq!(body, "plain  text");
+/// # });
+/// ```
+pub trait ResponseRewriter: Send + Sync {
+    /// Returns the new body for a response with the given head, which may be changed too.
+    fn rewrite(&self, parts: &mut Parts, body: Bytes) -> impl Future<Output = Bytes> + Send;
+}
+
+/// The minifier as a rewriter, doing everything the middleware does with a response to a
+/// `GET /` request, as [`minify_response`] does.
+impl ResponseRewriter for MinifierConfig {
+    async fn rewrite(&self, parts: &mut Parts, body: Bytes) -> Bytes {
+        let head = std::mem::replace(parts, Response::new(()).into_parts().0);
+        let (head, body) = minify_response(Response::from_parts(head, Body::from(body)), self).await.into_parts();
+        *parts = head;
+        // The body is already in memory, unless streaming mode is configured
+        body.collect().await.map(|collected| collected.to_bytes()).unwrap_or_default()
+    }
+}
+
+impl<A: ResponseRewriter, B: ResponseRewriter> ResponseRewriter for (A, B) {
+    async fn rewrite(&self, parts: &mut Parts, body: Bytes) -> Bytes {
+        let body = self.0.rewrite(parts, body).await;
+        self.1.rewrite(parts, body).await
+    }
+}
+
+/// Layer that buffers responses of the wrapped service and runs them through a
+/// [`ResponseRewriter`].
+///
+/// Responses whose body can't be rewritten safely are forwarded untouched: ranges, encoded
+/// bodies, bodies that are never sent, bodies ending in trailers, event streams and upgrades.
+/// Rewriters see every other response and decide for themselves which to change.
+#[derive(Debug, Default)]
+pub struct RewriteLayer<R> {
+    rewriter: Arc<R>,
+    max_body_bytes: Option<usize>,
+}
+
+impl<R> Clone for RewriteLayer<R> {
+    fn clone(&self) -> Self {
+        Self {
+            rewriter: self.rewriter.clone(),
+            max_body_bytes: self.max_body_bytes,
+        }
+    }
+}
+
+impl<R> RewriteLayer<R> {
+    /// Creates a layer running responses through `rewriter`.
+    pub fn new(rewriter: R) -> Self {
+        Self {
+            rewriter: Arc::new(rewriter),
+            max_body_bytes: None,
+        }
+    }
+
+    /// Forwards bodies over `limit` bytes without rewriting them. Defaults to no limit.
+    pub fn max_body_bytes(mut self, limit: usize) -> Self {
+        self.max_body_bytes = Some(limit);
+        self
+    }
+}
+
+impl<S, R> Layer<S> for RewriteLayer<R> {
+    type Service = RewriteService<S, R>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RewriteService {
+            inner,
+            rewriter: self.rewriter.clone(),
+            max_body_bytes: self.max_body_bytes,
+        }
+    }
+}
+
+/// Service produced by [`RewriteLayer`].
+#[derive(Debug)]
+pub struct RewriteService<S, R> {
+    inner: S,
+    rewriter: Arc<R>,
+    max_body_bytes: Option<usize>,
+}
+
+impl<S: Clone, R> Clone for RewriteService<S, R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            rewriter: self.rewriter.clone(),
+            max_body_bytes: self.max_body_bytes,
+        }
+    }
+}
+
+impl<S, R, B> Service<Request<B>> for RewriteService<S, R>
+where
+    S: Service<Request<B>, Response = Response<Body>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    R: ResponseRewriter + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<Body>;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Take the service that was driven to readiness, leaving a fresh clone behind
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let rewriter = self.rewriter.clone();
+        let max_body_bytes = self.max_body_bytes;
+
+        Box::pin(async move {
+            let response = inner.call(req).await?;
+            Ok(rewrite_response(&*rewriter, max_body_bytes, response).await)
+        })
+    }
+}
+
+/// Buffers `response` and runs it through `rewriter`, unless it must be forwarded as it is.
+async fn rewrite_response<R: ResponseRewriter>(
+    rewriter: &R,
+    max_body_bytes: Option<usize>,
+    response: Response<Body>,
+) -> Response<Body> {
+    let (mut parts, body) = response.into_parts();
+    if !rewritable(&parts) {
+        return Response::from_parts(parts, body);
+    }
+    let original = match response_buffer(body, max_body_bytes, false).await {
+        Ok(Buffered::Complete(bytes)) => bytes,
+        Ok(Buffered::TooLarge(body) | Buffered::Trailers(body)) => return Response::from_parts(parts, body),
+        Err(err) => return err.into_response(),
+    };
+
+    let rewritten = rewriter.rewrite(&mut parts, original.clone()).await;
+    if rewritten != original {
+        parts.headers.remove(TRANSFER_ENCODING);
+        if parts.headers.contains_key(CONTENT_LENGTH) {
+            parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(rewritten.len()));
+        }
+        headers::weaken_etag(&mut parts.headers);
+        headers::refuse_ranges(&mut parts.headers);
+    }
+    Response::from_parts(parts, Body::from(rewritten))
+}
+
+/// Whether the body of a response with this head may be buffered and replaced.
+fn rewritable(parts: &Parts) -> bool {
+    let is_encoded = parts
+        .headers
+        .get_all(CONTENT_ENCODING)
+        .iter()
+        .any(|v| !v.as_bytes().eq_ignore_ascii_case(b"identity"));
+    let is_upgrade = parts.status == StatusCode::SWITCHING_PROTOCOLS || headers::is_upgrade(&parts.headers);
+    let is_partial = parts.status == StatusCode::PARTIAL_CONTENT || parts.headers.contains_key(CONTENT_RANGE);
+    let is_empty = parts.status == StatusCode::NO_CONTENT || parts.status == StatusCode::NOT_MODIFIED;
+    !(is_encoded || is_upgrade || is_partial || is_empty || content_type::is_event_stream(&parts.headers))
+}
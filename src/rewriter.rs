@@ -0,0 +1,285 @@
+//! Streaming minification of HTML bodies with `lol_html`.
+//!
+//! Unlike [`StreamingMinifier`](crate::StreamingMinifier), this parses the document the way a
+//! browser would, so whitespace is only collapsed in text that renders as such: not in
+//! `<script>`, `<style>`, `<textarea>`, `<title>` or `<pre>`, nor inside tags or comments.
+
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+use lol_html::{
+    doc_comments, doc_text, element,
+    html_content::{ContentType, EndTag, TextChunk, TextType},
+    send::{HtmlRewriter, Settings},
+    HandlerResult, OutputSink,
+};
+
+use crate::{
+    protect::{NO_MINIFY_END, NO_MINIFY_START},
+    stream::{verbatim_tags, VERBATIM_TAGS},
+    MinifierConfig,
+};
+
+/// Body that collapses whitespace in HTML text as frames pass through, using `lol_html`.
+///
+/// `lol_html` holds back only what it needs to finish parsing a token, so memory stays flat
+/// and output follows input closely. If rewriting fails, the rest of the body is forwarded as
+/// it arrives. [`LolHtmlMinifier::with_config`] also keeps the regions a [`MinifierConfig`]
+/// preserves, as [`StreamingMinifier::with_config`](crate::StreamingMinifier::with_config) does.
+///
+/// ```
+/// use axum::body::Body;
+/// use axum_mini::{minify_html_bytes, LolHtmlMinifier, MinifierConfig};
+/// use http_body_util::BodyExt;
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let html = "<ul>\n  <li>  One  </li>\n  <li>Two</li>\n</ul>\n<pre>  kept  </pre>";
+/// let streamed = LolHtmlMinifier::new(Body::from(html)).collect().await.unwrap().to_bytes();
+/// assert_eq!(streamed, "<ul>\n<li> One </li>\n<li>Two</li>\n</ul>\n<pre>  kept  </pre>");
+///
+/// // Less thorough than the buffered path, but the same document
+/// let buffered = minify_html_bytes(html.as_bytes(), &MinifierConfig::default());
+/// assert_eq!(minify_html_bytes(&streamed, &MinifierConfig::default()), buffered);
+/// # });
+/// ```
+pub struct LolHtmlMinifier<B> {
+    inner: B,
+    rewriter: Option<HtmlRewriter<'static, Sink>>,
+    output: Arc<Mutex<Vec<u8>>>,
+    trailers: Option<Frame<Bytes>>,
+    done: bool,
+}
+
+impl<B> LolHtmlMinifier<B> {
+    /// Wraps an HTML body.
+    pub fn new(inner: B) -> Self {
+        Self::with_tags(inner, VERBATIM_TAGS.iter().map(|tag| tag.to_string()).collect(), false)
+    }
+
+    /// Wraps an HTML body, also keeping the whitespace of the regions `cfg` preserves: its
+    /// [`preserve_whitespace_tags`](MinifierConfig::preserve_whitespace_tags), `<code>` and
+    /// `<noscript>` as its settings for them ask, and `<!--minify:off-->` regions when it
+    /// [honors the markers](MinifierConfig::honor_no_minify_markers).
+    pub fn with_config(inner: B, cfg: &MinifierConfig) -> Self {
+        Self::with_tags(inner, verbatim_tags(cfg), cfg.honor_no_minify_markers)
+    }
+
+    fn with_tags(inner: B, tags: Vec<String>, honor_markers: bool) -> Self {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let depth = Arc::new(Depth::default());
+        let mut element_content_handlers = Vec::new();
+        // Names that aren't valid selectors can't match an element either
+        for tag in tags.iter().filter(|tag| is_tag_name(tag)) {
+            let depth = depth.clone();
+            element_content_handlers.push(element!(tag.as_str(), move |el| {
+                // Void and self-closing elements have no content, and no end tag to wait for
+                if let Some(handlers) = el.end_tag_handlers() {
+                    depth.elements.fetch_add(1, Ordering::Relaxed);
+                    let depth = depth.clone();
+                    let on_end: EndTagHandler = Box::new(move |_end| {
+                        depth.elements.fetch_sub(1, Ordering::Relaxed);
+                        Ok(())
+                    });
+                    handlers.push(on_end);
+                }
+                Ok(())
+            }));
+        }
+        let mut document_content_handlers = Vec::new();
+        if honor_markers {
+            let depth = depth.clone();
+            document_content_handlers.push(doc_comments!(move |comment| {
+                let text = format!("<!--{}-->", comment.text());
+                if text.as_bytes() == NO_MINIFY_START {
+                    depth.markers.fetch_add(1, Ordering::Relaxed);
+                } else if text.as_bytes() == NO_MINIFY_END {
+                    // A stray end marker closes nothing
+                    let _ = depth.markers.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+                }
+                Ok(())
+            }));
+        }
+        // Whether the text written last ended in collapsed whitespace; runs may span chunks
+        let mut in_space = false;
+        document_content_handlers.push(doc_text!(move |chunk| {
+            if depth.is_zero() {
+                collapse(chunk, &mut in_space);
+            } else {
+                in_space = false;
+            }
+            Ok(())
+        }));
+        let settings = Settings {
+            element_content_handlers,
+            document_content_handlers,
+            strict: false,
+            ..Settings::new_send()
+        };
+        Self {
+            inner,
+            rewriter: Some(HtmlRewriter::new(settings, Sink(output.clone()))),
+            output,
+            trailers: None,
+            done: false,
+        }
+    }
+
+    /// Feeds `data` to the rewriter, returning whatever output it produced.
+    fn rewrite(&mut self, data: &[u8]) -> Vec<u8> {
+        let failed = match &mut self.rewriter {
+            Some(rewriter) => rewriter.write(data).is_err(),
+            None => return data.to_vec(),
+        };
+        let mut out = std::mem::take(&mut *self.output.lock().expect("output lock poisoned"));
+        if failed {
+            self.rewriter = None;
+            out.extend_from_slice(data);
+        }
+        out
+    }
+
+    /// Flushes the rewriter at the end of the document.
+    fn finish(&mut self) -> Vec<u8> {
+        if let Some(rewriter) = self.rewriter.take() {
+            // Nothing can be recovered from a failure this late; send what was produced
+            let _ = rewriter.end();
+        }
+        std::mem::take(&mut *self.output.lock().expect("output lock poisoned"))
+    }
+}
+
+/// The end tag handlers of a `Send` rewriter.
+type EndTagHandler = Box<dyn FnOnce(&mut EndTag<'_>) -> HandlerResult + Send>;
+
+/// How deep the rewriter is in regions whose text is kept as it is.
+#[derive(Default)]
+struct Depth {
+    /// Verbatim elements entered and not yet closed.
+    elements: AtomicUsize,
+    /// `<!--minify:off-->` markers not yet matched by a `<!--minify:on-->`.
+    markers: AtomicUsize,
+}
+
+impl Depth {
+    fn is_zero(&self) -> bool {
+        self.elements.load(Ordering::Relaxed) == 0 && self.markers.load(Ordering::Relaxed) == 0
+    }
+}
+
+/// Whether `tag` is a plain element name, usable as a selector as it is.
+fn is_tag_name(tag: &str) -> bool {
+    tag.starts_with(|c: char| c.is_ascii_alphabetic()) && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Collapses the whitespace in a chunk of the document's text.
+fn collapse(chunk: &mut TextChunk<'_>, in_space: &mut bool) {
+    // Raw text, such as scripts and styles, and escapable raw text, such as `<textarea>`
+    if chunk.text_type() != TextType::Data {
+        *in_space = false;
+        return;
+    }
+    let text = chunk.as_str();
+    let mut out = String::with_capacity(text.len());
+    let mut run: Option<char> = None;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            let newline = c == '\n' || run == Some('\n');
+            run = Some(if newline { '\n' } else { ' ' });
+            continue;
+        }
+        if let Some(ws) = run.take() {
+            if !(out.is_empty() && *in_space) {
+                out.push(ws);
+            }
+        }
+        *in_space = false;
+        out.push(c);
+    }
+    if let Some(ws) = run {
+        if !*in_space {
+            out.push(ws);
+        }
+        *in_space = true;
+    }
+    if chunk.last_in_text_node() {
+        *in_space = false;
+    }
+    if out != text {
+        chunk.replace(&out, ContentType::Html);
+    }
+}
+
+/// Collects the rewriter's output until the body hands it on.
+struct Sink(Arc<Mutex<Vec<u8>>>);
+
+impl OutputSink for Sink {
+    fn handle_chunk(&mut self, chunk: &[u8]) {
+        self.0.lock().expect("output lock poisoned").extend_from_slice(chunk);
+    }
+}
+
+impl<B> HttpBody for LolHtmlMinifier<B>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, B::Error>>> {
+        let this = &mut *self;
+        loop {
+            if this.done {
+                return Poll::Ready(this.trailers.take().map(Ok));
+            }
+            match Pin::new(&mut this.inner).poll_frame(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(frame))) => match frame.into_data() {
+                    Ok(data) => {
+                        let out = this.rewrite(&data);
+                        if !out.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(Bytes::from(out)))));
+                        }
+                    }
+                    // Trailers end the body, so flush the rewriter before them
+                    Err(trailers) => {
+                        this.trailers = Some(trailers);
+                        this.done = true;
+                        let rest = this.finish();
+                        if !rest.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(Bytes::from(rest)))));
+                        }
+                    }
+                },
+                Poll::Ready(None) => {
+                    this.done = true;
+                    let rest = this.finish();
+                    if !rest.is_empty() {
+                        return Poll::Ready(Some(Ok(Frame::data(Bytes::from(rest)))));
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done && self.trailers.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        // Collapsing only ever shrinks the body
+        let mut hint = SizeHint::new();
+        if let Some(upper) = self.inner.size_hint().upper() {
+            hint.set_upper(upper);
+        }
+        hint
+    }
+}
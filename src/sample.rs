@@ -0,0 +1,42 @@
+//! Cheap random sampling of responses.
+
+use std::{
+    cell::Cell,
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+thread_local! {
+    static STATE: Cell<u64> = Cell::new(seed());
+}
+
+/// Returns `true` for roughly a `rate` fraction of calls.
+pub(crate) fn sampled(rate: f64) -> bool {
+    if rate >= 1.0 {
+        return true;
+    }
+    if rate <= 0.0 || rate.is_nan() {
+        return false;
+    }
+    // The top 53 bits make a uniform float in [0, 1)
+    let draw = (next() >> 11) as f64 / (1u64 << 53) as f64;
+    draw < rate
+}
+
+/// Advances this thread's xorshift64* generator.
+fn next() -> u64 {
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        state.set(x);
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    })
+}
+
+/// Seeds the generator from the per-process random keys std uses for `HashMap`.
+fn seed() -> u64 {
+    // xorshift gets stuck at zero
+    RandomState::new().build_hasher().finish() | 1
+}
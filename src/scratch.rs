@@ -0,0 +1,50 @@
+//! Per-thread scratch buffers for the passes run after `minify_html`.
+
+use std::cell::RefCell;
+
+/// Buffers with more capacity than this are freed instead of kept, so that one huge document
+/// doesn't pin its memory to the thread for good.
+const MAX_RETAINED_BYTES: usize = 1 << 20;
+
+/// A pass rewriting a document, appending the result to the output buffer.
+pub(crate) type Pass = fn(&[u8], &mut Vec<u8>);
+
+thread_local! {
+    static SCRATCH: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Runs each of `passes` over `html` in turn, writing into this thread's scratch buffer and
+/// swapping it with `html` after every pass.
+///
+/// The buffer is cleared before each pass, so nothing from an earlier call can leak into the
+/// output. Once warm, a chain of passes allocates nothing beyond what the passes themselves do.
+pub(crate) fn run_passes(html: &mut Vec<u8>, passes: impl IntoIterator<Item = Pass>) {
+    let mut passes = passes.into_iter().peekable();
+    if passes.peek().is_none() {
+        return;
+    }
+    SCRATCH.with_borrow_mut(|scratch| {
+        for pass in passes {
+            scratch.clear();
+            pass(html, scratch);
+            std::mem::swap(html, scratch);
+        }
+        scratch.clear();
+        if scratch.capacity() > MAX_RETAINED_BYTES {
+            *scratch = Vec::new();
+        }
+    });
+}
+
+/// Keeps `buf` as this thread's scratch buffer if it is roomier than the current one.
+pub(crate) fn recycle(mut buf: Vec<u8>) {
+    if buf.capacity() > MAX_RETAINED_BYTES {
+        return;
+    }
+    SCRATCH.with_borrow_mut(|scratch| {
+        if buf.capacity() > scratch.capacity() {
+            buf.clear();
+            *scratch = buf;
+        }
+    });
+}
@@ -0,0 +1,139 @@
+//! Per-response minification statistics, lifetime totals across responses, and why bodies
+//! were skipped.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Outcome of minifying a response, available as a response extension.
+///
+/// Inserted for every buffered response of a minifiable content type, so layers running
+/// after the minifier can read it with `response.extensions().get::<MinifyStats>()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MinifyStats {
+    /// Body length before minification, in bytes.
+    pub original_len: usize,
+    /// Body length as sent, in bytes. In dry-run mode, the length that would have been sent.
+    pub final_len: usize,
+    /// Whether the minifier actually ran on the body.
+    pub minified: bool,
+}
+
+impl MinifyStats {
+    /// Bytes removed from the body.
+    pub fn saved_bytes(&self) -> usize {
+        self.original_len.saturating_sub(self.final_len)
+    }
+
+    /// Percentage of the original body that was removed.
+    pub fn reduction_pct(&self) -> f64 {
+        if self.original_len == 0 {
+            0.0
+        } else {
+            self.saved_bytes() as f64 * 100.0 / self.original_len as f64
+        }
+    }
+}
+
+/// Why a body the middleware would have minified was sent as it was, passed to
+/// [`MinifierConfig::on_skip`](crate::MinifierConfig::on_skip).
+///
+/// Only failures are reported; bodies skipped by choice, such as when the client opted out or
+/// the body is below [`MinifierConfig::min_size_bytes`](crate::MinifierConfig::min_size_bytes),
+/// are not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SkipReason {
+    /// The body exceeded [`MinifierConfig::max_body_bytes`](crate::MinifierConfig::max_body_bytes).
+    TooLarge,
+    /// The body didn't match its `Content-Length`, with
+    /// [`MinifierConfig::strict_content_length`](crate::MinifierConfig::strict_content_length) set.
+    LengthMismatch,
+    /// The body's declared charset couldn't be converted to and from UTF-8.
+    Charset,
+    /// The body wasn't valid UTF-8.
+    InvalidUtf8,
+    /// The minifier panicked.
+    Panic,
+    /// The minifier ran past [`MinifierConfig::timeout`](crate::MinifierConfig::timeout).
+    Timeout,
+    /// Every [`MinifierConfig::max_concurrent`](crate::MinifierConfig::max_concurrent) permit
+    /// was taken.
+    Busy,
+    /// The [`MinifierConfig::cpu_budget`](crate::MinifierConfig::cpu_budget) was spent.
+    OverBudget,
+    /// The minified body lost visible text, as found with
+    /// [`MinifierConfig::verify_rate`](crate::MinifierConfig::verify_rate).
+    Diverged,
+}
+
+/// Lifetime totals across every response the middleware handled, e.g. for an admin page.
+///
+/// Share one collector by putting it in
+/// [`MinifierConfig::stats_collector`](crate::MinifierConfig::stats_collector); updating it is
+/// a handful of relaxed atomic additions per response.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use axum::{body::Body, http::{header, Response}};
+/// use axum_mini::{minify_response, MinifierConfig, MinifyStatsCollector};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let collector = Arc::new(MinifyStatsCollector::new());
+/// let config = MinifierConfig::builder().stats_collector(collector.clone()).build();
+/// for (content_type, body) in [("text/html", "<p>  Hello  </p>"), ("text/html", "<p>  World  </p>"), ("text/plain", "  Hi  ")] {
+///     let response = Response::builder().header(header::CONTENT_TYPE, content_type).body(Body::from(body)).unwrap();
+///     minify_response(response, &config).await;
+/// }
+///
+/// assert_eq!(collector.responses(), 3);
+/// assert_eq!(collector.minified(), 2);
+/// assert_eq!(collector.original_bytes(), 32);
+/// assert_eq!(collector.final_bytes(), 16);
+/// # });
+/// ```
+#[derive(Debug, Default)]
+pub struct MinifyStatsCollector {
+    responses: AtomicU64,
+    minified: AtomicU64,
+    original_bytes: AtomicU64,
+    final_bytes: AtomicU64,
+}
+
+impl MinifyStatsCollector {
+    /// Creates a collector with every total at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Responses the middleware handled, whether or not they were minified.
+    pub fn responses(&self) -> u64 {
+        self.responses.load(Ordering::Relaxed)
+    }
+
+    /// Responses whose body went through the minifier.
+    pub fn minified(&self) -> u64 {
+        self.minified.load(Ordering::Relaxed)
+    }
+
+    /// Bytes of minifiable bodies before minification, as in [`MinifyStats::original_len`].
+    pub fn original_bytes(&self) -> u64 {
+        self.original_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Bytes of minifiable bodies as sent, as in [`MinifyStats::final_len`].
+    pub fn final_bytes(&self) -> u64 {
+        self.final_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Adds one response, with its stats if its body was of a minifiable type.
+    #[cfg(feature = "axum")]
+    pub(crate) fn record(&self, stats: Option<&MinifyStats>) {
+        self.responses.fetch_add(1, Ordering::Relaxed);
+        let Some(stats) = stats else { return };
+        if stats.minified {
+            self.minified.fetch_add(1, Ordering::Relaxed);
+        }
+        self.original_bytes.fetch_add(stats.original_len as u64, Ordering::Relaxed);
+        self.final_bytes.fetch_add(stats.final_len as u64, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,324 @@
+//! Streaming minification of HTML bodies.
+//!
+//! Full minification needs the whole document, so streaming mode applies a cheaper,
+//! chunk-boundary-safe pass instead: runs of whitespace in text collapse to a single
+//! character, while tags, comments and the content of `<script>`, `<style>`, `<pre>` and
+//! `<textarea>` pass through verbatim, along with the other regions a [`MinifierConfig`]
+//! preserves.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http_body::{Body as HttpBody, Frame, SizeHint};
+
+use crate::{
+    protect::{NO_MINIFY_END, NO_MINIFY_START},
+    MinifierConfig,
+};
+
+/// Elements whose content is always copied verbatim.
+pub(crate) const VERBATIM_TAGS: [&str; 4] = ["script", "style", "pre", "textarea"];
+
+/// Lowercase names of the elements whose content `cfg` keeps verbatim.
+pub(crate) fn verbatim_tags(cfg: &MinifierConfig) -> Vec<String> {
+    let mut tags: Vec<String> = VERBATIM_TAGS.iter().map(|tag| tag.to_string()).collect();
+    tags.extend(cfg.preserve_whitespace_tags.iter().map(|tag| tag.to_ascii_lowercase()));
+    if cfg.preserve_sensitive_whitespace {
+        tags.push("code".to_owned());
+    }
+    if !cfg.minify_noscript {
+        tags.push("noscript".to_owned());
+    }
+    tags
+}
+
+/// Body that collapses whitespace in HTML as frames pass through, without buffering.
+///
+/// Each data frame is forwarded as soon as it has been processed; at most one byte of
+/// trailing whitespace is held back until the next frame shows whether the run continues.
+pub struct StreamingMinifier<B> {
+    inner: B,
+    collapser: WhitespaceCollapser,
+    trailers: Option<Frame<Bytes>>,
+    done: bool,
+}
+
+impl<B> StreamingMinifier<B> {
+    /// Wraps an HTML body.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            collapser: WhitespaceCollapser::default(),
+            trailers: None,
+            done: false,
+        }
+    }
+
+    /// Wraps an HTML body, also copying verbatim the regions `cfg` preserves: the content of
+    /// its [`preserve_whitespace_tags`](MinifierConfig::preserve_whitespace_tags), of
+    /// `<code>` with [`preserve_sensitive_whitespace`](MinifierConfig::preserve_sensitive_whitespace),
+    /// of `<noscript>` without [`minify_noscript`](MinifierConfig::minify_noscript), and
+    /// regions marked with [`honor_no_minify_markers`](MinifierConfig::honor_no_minify_markers).
+    pub fn with_config(inner: B, cfg: &MinifierConfig) -> Self {
+        Self {
+            collapser: WhitespaceCollapser::for_config(cfg),
+            ..Self::new(inner)
+        }
+    }
+}
+
+impl<B> HttpBody for StreamingMinifier<B>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+{
+    type Data = Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, B::Error>>> {
+        let this = &mut *self;
+        loop {
+            if this.done {
+                return Poll::Ready(this.trailers.take().map(Ok));
+            }
+            match Pin::new(&mut this.inner).poll_frame(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(Some(Ok(frame))) => match frame.into_data() {
+                    Ok(data) => {
+                        let mut out = Vec::with_capacity(data.len());
+                        this.collapser.feed(&data, &mut out);
+                        if !out.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(Bytes::from(out)))));
+                        }
+                    }
+                    // Trailers end the body, so flush held-back whitespace before them
+                    Err(trailers) => {
+                        this.trailers = Some(trailers);
+                        this.done = true;
+                        if let Some(rest) = this.collapser.finish() {
+                            return Poll::Ready(Some(Ok(Frame::data(rest))));
+                        }
+                    }
+                },
+                Poll::Ready(None) => {
+                    this.done = true;
+                    if let Some(rest) = this.collapser.finish() {
+                        return Poll::Ready(Some(Ok(Frame::data(rest))));
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done && self.trailers.is_none() && self.collapser.pending.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        let mut hint = SizeHint::new();
+        if let Some(upper) = self.inner.size_hint().upper() {
+            hint.set_upper(upper + u64::from(self.collapser.pending.is_some()));
+        }
+        hint
+    }
+}
+
+/// Where in the document the collapser is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Text,
+    /// Just after `<`, reading the tag name.
+    TagName { closing: bool },
+    /// Inside a tag, after its name; `verbatim` indexes the verbatim tag it opens.
+    Tag { quote: Option<u8>, verbatim: Option<usize> },
+    /// Inside `<!-- ... -->`, counting the dashes just seen.
+    Comment { dashes: u8 },
+    /// Inside the content of a verbatim element, matching its `</tag` so far.
+    Verbatim { tag: usize, matched: usize },
+    /// Inside a region marked `<!--minify:off-->`, as many levels deep as `depth`.
+    Marked { depth: usize },
+}
+
+/// Incremental whitespace collapser that can be fed a document in arbitrary chunks.
+#[derive(Debug)]
+pub(crate) struct WhitespaceCollapser {
+    state: State,
+    /// Collapsed whitespace not yet written; a newline if the run contained one.
+    pending: Option<u8>,
+    /// The tag name being read, a comment's start or the last bytes of a marked region.
+    name: Vec<u8>,
+    /// Lowercase names of the elements whose content is copied verbatim.
+    verbatim_tags: Vec<Vec<u8>>,
+    /// Longest tag name worth remembering; anything longer can't be a verbatim tag.
+    max_name: usize,
+    /// Whether `<!--minify:off-->` regions are copied verbatim.
+    honor_markers: bool,
+}
+
+impl Default for WhitespaceCollapser {
+    fn default() -> Self {
+        Self::with_tags(VERBATIM_TAGS.iter().map(|tag| tag.as_bytes().to_vec()).collect(), false)
+    }
+}
+
+impl WhitespaceCollapser {
+    /// A collapser that also leaves alone the regions `cfg` preserves.
+    pub(crate) fn for_config(cfg: &MinifierConfig) -> Self {
+        let tags = verbatim_tags(cfg).into_iter().map(String::into_bytes).collect();
+        Self::with_tags(tags, cfg.honor_no_minify_markers)
+    }
+
+    fn with_tags(verbatim_tags: Vec<Vec<u8>>, honor_markers: bool) -> Self {
+        let longest = verbatim_tags.iter().map(Vec::len).max().unwrap_or_default();
+        Self {
+            state: State::default(),
+            pending: None,
+            name: Vec::new(),
+            // One more than the longest, so that longer names never match once truncated
+            max_name: (longest + 1).max(NO_MINIFY_START.len()),
+            verbatim_tags,
+            honor_markers,
+        }
+    }
+
+    /// Collapses the whitespace of a whole document.
+    pub(crate) fn collapse(mut self, input: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(input.len());
+        self.feed(input, &mut out);
+        out.extend_from_slice(&self.finish().unwrap_or_default());
+        out
+    }
+
+    /// Processes the next chunk of the document, appending output to `out`.
+    pub(crate) fn feed(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &byte in input {
+            self.step(byte, out);
+        }
+    }
+
+    /// Returns whitespace still held back at the end of the document.
+    pub(crate) fn finish(&mut self) -> Option<Bytes> {
+        self.pending.take().map(|ws| Bytes::from(vec![ws]))
+    }
+
+    fn step(&mut self, byte: u8, out: &mut Vec<u8>) {
+        match self.state {
+            State::Text => self.text(byte, out),
+            State::TagName { closing } => {
+                if self.name.is_empty() && byte == b'/' && !closing {
+                    self.state = State::TagName { closing: true };
+                    out.push(byte);
+                } else if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'!' {
+                    if self.name.len() < self.max_name {
+                        self.name.push(byte.to_ascii_lowercase());
+                    }
+                    out.push(byte);
+                    if self.name == b"!--" {
+                        self.state = State::Comment { dashes: 0 };
+                    }
+                } else if self.name.is_empty() {
+                    // A lone `<` in text, e.g. `a < b`
+                    self.state = State::Text;
+                    self.text(byte, out);
+                } else {
+                    let verbatim = if closing {
+                        None
+                    } else {
+                        self.verbatim_tags.iter().position(|tag| *tag == self.name)
+                    };
+                    self.state = State::Tag { quote: None, verbatim };
+                    self.step(byte, out);
+                }
+            }
+            State::Tag { quote, verbatim } => {
+                out.push(byte);
+                match quote {
+                    Some(q) if byte == q => self.state = State::Tag { quote: None, verbatim },
+                    Some(_) => {}
+                    None if byte == b'"' || byte == b'\'' => {
+                        self.state = State::Tag { quote: Some(byte), verbatim };
+                    }
+                    None if byte == b'>' => {
+                        self.state = match verbatim {
+                            Some(tag) => State::Verbatim { tag, matched: 0 },
+                            None => State::Text,
+                        };
+                    }
+                    None => {}
+                }
+            }
+            State::Comment { dashes } => {
+                out.push(byte);
+                // Remember enough of the comment to recognize `<!--minify:off-->`
+                if self.name.len() < self.max_name {
+                    self.name.push(byte);
+                }
+                self.state = match byte {
+                    b'>' if dashes >= 2 && self.honor_markers && self.name == NO_MINIFY_START[1..] => {
+                        self.name.clear();
+                        State::Marked { depth: 1 }
+                    }
+                    b'>' if dashes >= 2 => State::Text,
+                    b'-' => State::Comment { dashes: dashes.saturating_add(1) },
+                    _ => State::Comment { dashes: 0 },
+                };
+            }
+            State::Marked { depth } => {
+                out.push(byte);
+                // Keep a window of the last bytes, as long as the longer marker
+                if self.name.len() == NO_MINIFY_START.len() {
+                    self.name.remove(0);
+                }
+                self.name.push(byte);
+                self.state = if self.name.ends_with(NO_MINIFY_START) {
+                    State::Marked { depth: depth + 1 }
+                } else if self.name.ends_with(NO_MINIFY_END) && depth == 1 {
+                    State::Text
+                } else if self.name.ends_with(NO_MINIFY_END) {
+                    State::Marked { depth: depth - 1 }
+                } else {
+                    State::Marked { depth }
+                };
+            }
+            State::Verbatim { tag, matched } => {
+                out.push(byte);
+                let name = &self.verbatim_tags[tag];
+                let expected = match matched {
+                    0 => b'<',
+                    1 => b'/',
+                    n => name[n - 2],
+                };
+                self.state = if byte.to_ascii_lowercase() == expected {
+                    if matched + 1 == name.len() + 2 {
+                        State::Tag { quote: None, verbatim: None }
+                    } else {
+                        State::Verbatim { tag, matched: matched + 1 }
+                    }
+                } else if byte == b'<' {
+                    State::Verbatim { tag, matched: 1 }
+                } else {
+                    State::Verbatim { tag, matched: 0 }
+                };
+            }
+        }
+    }
+
+    fn text(&mut self, byte: u8, out: &mut Vec<u8>) {
+        if byte.is_ascii_whitespace() {
+            self.pending = Some(if byte == b'\n' || self.pending == Some(b'\n') { b'\n' } else { b' ' });
+            return;
+        }
+        if let Some(ws) = self.pending.take() {
+            out.push(ws);
+        }
+        out.push(byte);
+        if byte == b'<' {
+            self.name.clear();
+            self.state = State::TagName { closing: false };
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Assertions pinning minified output in downstream test suites.
+
+use std::fmt::Write;
+
+use crate::{minify_html_bytes, MinifierConfig};
+
+/// Bytes of context shown on either side of the first difference.
+const CONTEXT: usize = 24;
+
+/// Asserts that [`minify_html_bytes`] turns `input` into exactly `expected` with `cfg`, e.g.
+/// to catch changes in a template's output when upgrading this crate or `minify_html`.
+///
+/// On a mismatch it panics with the first byte that differs, shown in context, and both
+/// documents in full.
+///
+/// ```
+/// use axum_mini::{assert_minifies_to, MinifierConfig};
+///
+/// assert_minifies_to("<ul>\n  <li>  One  </li>\n</ul>", "<ul><li>One</ul>", &MinifierConfig::default());
+/// ```
+///
+/// ```should_panic
+/// use axum_mini::{assert_minifies_to, MinifierConfig};
+///
+/// // Panics with:
+/// //   minified output differs from expected at byte 13
+/// //     expected: <ul><li>One</li></ul>
+/// //     actual:   <ul><li>One</ul>
+/// //                            ^
+/// assert_minifies_to("<ul>\n  <li>  One  </li>\n</ul>", "<ul><li>One</li></ul>", &MinifierConfig::default());
+/// ```
+#[track_caller]
+pub fn assert_minifies_to(input: impl AsRef<[u8]>, expected: impl AsRef<[u8]>, cfg: &MinifierConfig) {
+    let actual = minify_html_bytes(input.as_ref(), cfg);
+    let expected = expected.as_ref();
+    if actual != expected {
+        panic!("{}", mismatch(expected, &actual));
+    }
+}
+
+/// Describes how `actual` differs from `expected`.
+fn mismatch(expected: &[u8], actual: &[u8]) -> String {
+    let at = expected.iter().zip(actual).take_while(|(e, a)| e == a).count();
+    let start = at.saturating_sub(CONTEXT);
+    let excerpt = |doc: &[u8]| {
+        let end = (at + CONTEXT).min(doc.len());
+        let prefix = if start > 0 { "…" } else { "" };
+        let suffix = if end < doc.len() { "…" } else { "" };
+        format!("{prefix}{}{suffix}", String::from_utf8_lossy(&doc[start..end]).escape_debug())
+    };
+    // Line the caret up with the first differing character of the excerpts
+    let before = String::from_utf8_lossy(&expected[start..at]).escape_debug().count() + usize::from(start > 0);
+
+    let mut message = format!("minified output differs from expected at byte {at}\n");
+    let _ = writeln!(message, "  expected: {}", excerpt(expected));
+    let _ = writeln!(message, "  actual:   {}", excerpt(actual));
+    let _ = writeln!(message, "            {}^", " ".repeat(before));
+    let _ = writeln!(message, "full expected: {:?}", String::from_utf8_lossy(expected));
+    let _ = write!(message, "full actual:   {:?}", String::from_utf8_lossy(actual));
+    message
+}
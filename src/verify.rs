@@ -0,0 +1,44 @@
+//! Check that minification kept a document's visible text.
+
+use crate::protect::{find, raw_text_end, tag_end};
+
+/// Whether `minified` shows the same text as `original`.
+///
+/// Only ASCII letters and digits outside tags, comments, scripts and styles are compared, so
+/// collapsed whitespace, dropped optional tags and respelled character references don't count
+/// as differences, while lost or garbled content does.
+pub(crate) fn same_text(original: &[u8], minified: &[u8]) -> bool {
+    visible_text(original) == visible_text(minified)
+}
+
+fn visible_text(html: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(html.len() / 2);
+    let mut i = 0;
+    while i < html.len() {
+        match html[i] {
+            b'<' if html[i..].starts_with(b"<!--") => {
+                i = find(html, b"-->", i + 4).map_or(html.len(), |close| close + 3);
+            }
+            b'<' if html.get(i + 1).is_some_and(|&b| b.is_ascii_alphabetic() || matches!(b, b'/' | b'!' | b'?')) => {
+                i = raw_text_end(html, i).or_else(|| tag_end(html, i)).unwrap_or(html.len());
+            }
+            // The minifier may shorten a reference, e.g. by dropping its `;`
+            b'&' => {
+                i += 1;
+                while html.get(i).is_some_and(|&b| b.is_ascii_alphanumeric() || b == b'#') {
+                    i += 1;
+                }
+                if html.get(i) == Some(&b';') {
+                    i += 1;
+                }
+            }
+            byte => {
+                if byte.is_ascii_alphanumeric() {
+                    out.push(byte);
+                }
+                i += 1;
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,83 @@
+//! Minification of XML and SVG responses.
+
+use crate::protect::{find, tag_end};
+
+/// SVG elements whose whitespace between children is rendered.
+const WHITESPACE_SENSITIVE_ELEMENTS: [&[u8]; 3] = [b"text", b"textPath", b"tspan"];
+
+/// Minifies an XML document by dropping comments and whitespace-only text between tags.
+///
+/// CDATA sections, processing instructions and the doctype are copied verbatim, as is any
+/// text inside elements with `xml:space="preserve"` or SVG text elements. Documents that
+/// can't be tokenized are returned unchanged.
+pub(crate) fn minify(input: &[u8]) -> Vec<u8> {
+    try_minify(input).unwrap_or_else(|| input.to_vec())
+}
+
+fn try_minify(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len());
+    // Whether whitespace is significant in each open element
+    let mut preserve: Vec<bool> = Vec::new();
+    let mut i = 0;
+    while i < input.len() {
+        let rest = &input[i..];
+        let end = if rest.starts_with(b"<!--") {
+            i = find(input, b"-->", i + 4)? + 3;
+            continue;
+        } else if rest.starts_with(b"<![CDATA[") {
+            find(input, b"]]>", i + 9)? + 3
+        } else if rest.starts_with(b"<?") {
+            find(input, b"?>", i + 2)? + 2
+        } else if rest.starts_with(b"<!") {
+            doctype_end(input, i)?
+        } else if rest.starts_with(b"</") {
+            preserve.pop();
+            find(input, b">", i)? + 1
+        } else if rest.starts_with(b"<") {
+            let end = tag_end(input, i)?;
+            let tag = &input[i..end];
+            if !tag.ends_with(b"/>") {
+                let inherited = preserve.last().copied().unwrap_or(false);
+                preserve.push(inherited || preserves_whitespace(tag));
+            }
+            end
+        } else {
+            let end = find(input, b"<", i).unwrap_or(input.len());
+            let text = &input[i..end];
+            if text.iter().all(u8::is_ascii_whitespace) && !preserve.last().copied().unwrap_or(false) {
+                i = end;
+                continue;
+            }
+            end
+        };
+        out.extend_from_slice(&input[i..end]);
+        i = end;
+    }
+    Some(out)
+}
+
+/// Whether a start tag makes whitespace in its content significant.
+fn preserves_whitespace(tag: &[u8]) -> bool {
+    let name_end = tag
+        .iter()
+        .position(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'/')
+        .unwrap_or(tag.len());
+    let name = &tag[1.min(name_end)..name_end];
+    // Ignore any namespace prefix, e.g. `svg:text`
+    let local = name.rsplit(|&b| b == b':').next().unwrap_or(name);
+    WHITESPACE_SENSITIVE_ELEMENTS.contains(&local)
+        || find(tag, b"xml:space=\"preserve\"", 0).is_some()
+        || find(tag, b"xml:space='preserve'", 0).is_some()
+}
+
+/// Returns the index just past a `<!DOCTYPE ...>`, including any internal subset.
+fn doctype_end(input: &[u8], lt: usize) -> Option<usize> {
+    let gt = find(input, b">", lt)?;
+    match find(input, b"[", lt) {
+        Some(bracket) if bracket < gt => {
+            let close = find(input, b"]", bracket)?;
+            Some(find(input, b">", close)? + 1)
+        }
+        _ => Some(gt + 1),
+    }
+}
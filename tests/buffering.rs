@@ -0,0 +1,72 @@
+//! How bodies are collected before minification.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::convert::Infallible;
+
+use axum::{
+    body::{Body, Bytes},
+    http::HeaderMap,
+};
+use axum_mini::{minify_html_bytes, minify_response, MinifierConfig};
+use common::{body_bytes, html, with_type};
+use futures_util::{stream, StreamExt};
+use http_body::Frame;
+use http_body_util::{BodyExt, StreamBody};
+
+#[tokio::test]
+async fn frames_of_any_size_are_reassembled_in_order() {
+    let rows = "<li>  row  </li>\n".repeat(500);
+    // Frames split tags and entities, and include empty and large ones
+    let frames: Vec<Bytes> = vec![
+        Bytes::from_static(b"<"),
+        Bytes::from_static(b"ul>\n  "),
+        Bytes::new(),
+        Bytes::from(rows.clone()),
+        Bytes::from_static(b"<li>caf&eac"),
+        Bytes::from_static(b"ute;</li"),
+        Bytes::from_static(b">\n</ul>  "),
+    ];
+    let page = frames.concat();
+    assert_eq!(page, format!("<ul>\n  {rows}<li>caf&eacute;</li>\n</ul>  ").into_bytes());
+
+    let body = Body::from_stream(stream::iter(frames.into_iter().map(Ok::<_, Infallible>)));
+    let cfg = MinifierConfig::default();
+    let response = minify_response(html(body), &cfg).await;
+    assert_eq!(body_bytes(response).await, minify_html_bytes(&page, &cfg));
+}
+
+#[tokio::test]
+async fn already_minified_bodies_are_forwarded_without_a_copy() {
+    let page = Bytes::from_static(b"<p>Hello, world!");
+    let response = minify_response(html(page.clone()), &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await.as_ptr(), page.as_ptr());
+}
+
+#[tokio::test]
+async fn other_types_stream_through_unbuffered() {
+    // 8 MiB now, and the rest of the file never arrives
+    let chunk = Bytes::from(vec![0; 8 << 20]);
+    let download = stream::iter([Ok::<_, Infallible>(chunk.clone())]).chain(stream::pending());
+
+    // Buffering would wait for the end of the body forever
+    let response = with_type("application/pdf", Body::from_stream(download));
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    let first = response.into_body().frame().await.unwrap().unwrap().into_data().unwrap();
+    assert_eq!(first.as_ptr(), chunk.as_ptr());
+}
+
+#[tokio::test]
+async fn bodies_ending_in_trailers_are_forwarded_whole() {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", "0".parse().unwrap());
+    let frames = [Frame::data(Bytes::from("<p>  Hello  </p>")), Frame::trailers(trailers)];
+    let body = Body::new(StreamBody::new(stream::iter(frames.map(Ok::<_, Infallible>))));
+
+    let response = minify_response(html(body), &MinifierConfig::default()).await;
+    let collected = response.into_body().collect().await.unwrap();
+    assert_eq!(collected.trailers().unwrap()["grpc-status"], "0");
+    assert_eq!(collected.to_bytes(), "<p>  Hello  </p>");
+}
@@ -0,0 +1,77 @@
+//! Reusing minified bodies across requests.
+
+#![cfg(feature = "cache")]
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    body::Body,
+    http::Request,
+    middleware::{self, Next},
+    response::{Html, Response},
+    routing::get,
+    Router,
+};
+use axum_mini::{minify_response, HtmlMinifierLayer, MinifierConfig, MinifierOverride, MinifyCache};
+use common::{body_bytes, html};
+
+const PAGE: &str = "<script>let  answer = 42 ;</script>";
+
+#[tokio::test]
+async fn overrides_do_not_share_cached_bodies() {
+    async fn keep_widget_js(mut req: Request<Body>, next: Next) -> Response {
+        if req.uri().path() == "/widget" {
+            req.extensions_mut().insert(MinifierOverride { minify_js: Some(false), ..Default::default() });
+        }
+        next.run(req).await
+    }
+
+    let cache = Arc::new(MinifyCache::new(16));
+    let mut app = Router::new()
+        .route("/", get(|| async { Html(PAGE) }))
+        .route("/widget", get(|| async { Html(PAGE) }))
+        .layer(HtmlMinifierLayer::with_config(MinifierConfig::builder().cache(cache.clone()).build()))
+        .layer(middleware::from_fn(keep_widget_js));
+
+    assert_eq!(body_bytes(common::get(&mut app, "/").await).await, "<script>let answer=42</script>");
+    assert_eq!(body_bytes(common::get(&mut app, "/widget").await).await, PAGE);
+    assert_eq!(cache.len(), 2);
+}
+
+#[tokio::test]
+async fn configs_sharing_a_cache_keep_their_own_output() {
+    let cache = Arc::new(MinifyCache::new(16));
+    let minify_js = MinifierConfig::builder().cache(cache.clone()).build();
+    let keep_js = MinifierConfig::builder().minify_js(false).cache(cache.clone()).build();
+
+    let body = body_bytes(minify_response(html(PAGE), &minify_js).await).await;
+    assert_eq!(body, "<script>let answer=42</script>");
+    let body = body_bytes(minify_response(html(PAGE), &keep_js).await).await;
+    assert_eq!(body, PAGE);
+}
+
+#[tokio::test]
+async fn same_body_hits_the_cache_on_second_call() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let cache = Arc::new(MinifyCache::new(16));
+    let cfg = MinifierConfig::builder()
+        .cache(cache.clone())
+        .minify_fn(move |input, cfg| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            minify_html::minify(input, cfg)
+        })
+        .build();
+
+    for _ in 0..2 {
+        let body = body_bytes(minify_response(html("<p>  Hello  </p>"), &cfg).await).await;
+        assert_eq!(body, "<p>Hello");
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!((cache.hits(), cache.misses()), (1, 1));
+}
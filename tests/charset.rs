@@ -0,0 +1,51 @@
+//! Bodies declared in legacy charsets.
+
+#![cfg(feature = "encoding")]
+
+mod common;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Method, Request},
+    middleware,
+    routing::post,
+    Router,
+};
+use axum_mini::{html_request_minifier, minify_response, MinifierConfig};
+use common::{body_bytes, with_type};
+
+#[tokio::test]
+async fn latin1_accents_survive_minification() {
+    // "<p>  Café   crème  </p>" in ISO-8859-1, where é and è are single bytes
+    let page: &[u8] = b"<p>  Caf\xe9   cr\xe8me  </p>";
+    let response = with_type("text/html; charset=ISO-8859-1", page);
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(response.headers()["content-type"], "text/html; charset=ISO-8859-1");
+    assert_eq!(body_bytes(response).await, &b"<p>Caf\xe9 cr\xe8me"[..]);
+}
+
+#[tokio::test]
+async fn unknown_charsets_pass_through() {
+    let page = "<p>  Hello  </p>";
+    let response = with_type("text/html; charset=x-nonsense", page);
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn latin1_requests_are_converted_back() {
+    let mut app = Router::new()
+        .route("/", post(|body: Bytes| async move { body }))
+        .layer(middleware::from_fn(html_request_minifier));
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/")
+        .header(header::CONTENT_TYPE, "text/html; charset=ISO-8859-1")
+        .body(Body::from(&b"<p>  Caf\xe9   cr\xe8me  </p>"[..]))
+        .unwrap();
+
+    let response = common::call(&mut app, request).await;
+    assert_eq!(body_bytes(response).await, &b"<p>Caf\xe9 cr\xe8me"[..]);
+}
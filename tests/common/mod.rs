@@ -0,0 +1,40 @@
+//! Helpers shared by the integration tests.
+
+// Each test crate uses a different subset
+#![allow(dead_code)]
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Request, Response},
+    Router,
+};
+use http_body_util::BodyExt;
+use tower::Service;
+
+/// A `text/html` response with `body`.
+pub fn html(body: impl Into<Body>) -> Response<Body> {
+    with_type("text/html", body)
+}
+
+/// A response with the given `Content-Type` and `body`.
+pub fn with_type(content_type: &str, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body.into())
+        .unwrap()
+}
+
+/// Reads the whole body of `response`.
+pub async fn body_bytes(response: Response<Body>) -> Bytes {
+    response.into_body().collect().await.unwrap().to_bytes()
+}
+
+/// Sends `req` through `app`.
+pub async fn call(app: &mut Router, req: Request<Body>) -> Response<Body> {
+    app.call(req).await.unwrap()
+}
+
+/// Sends a `GET` for `uri` through `app`.
+pub async fn get(app: &mut Router, uri: &str) -> Response<Body> {
+    call(app, Request::builder().uri(uri).body(Body::empty()).unwrap()).await
+}
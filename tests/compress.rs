@@ -0,0 +1,58 @@
+//! Compressing minified bodies with the `compress` feature.
+
+#![cfg(feature = "compress")]
+
+mod common;
+
+use std::io::Read;
+
+use axum::{
+    body::Body,
+    http::{header, Request},
+    response::Html,
+    routing::get,
+    Router,
+};
+use axum_mini::HtmlMinifierLayer;
+use common::body_bytes;
+
+/// Requests `/` with the given `Accept-Encoding`.
+fn accepting(encoding: &str) -> Request<Body> {
+    Request::builder().uri("/").header(header::ACCEPT_ENCODING, encoding).body(Body::empty()).unwrap()
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/", get(|| async { Html("<p>  Hello,   world!  </p>") }))
+        .layer(HtmlMinifierLayer::new())
+}
+
+#[tokio::test]
+async fn brotli_body_decompresses_to_minified_html() {
+    let response = common::call(&mut app(), accepting("gzip, br")).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+    assert!(response.headers().get_all(header::VARY).iter().any(|vary| vary == "accept-encoding"));
+
+    let compressed = body_bytes(response).await;
+    let mut html = String::new();
+    brotli::Decompressor::new(&compressed[..], 4096).read_to_string(&mut html).unwrap();
+    assert_eq!(html, "<p>Hello, world!");
+}
+
+#[tokio::test]
+async fn gzip_body_decompresses_to_minified_html() {
+    let response = common::call(&mut app(), accepting("gzip")).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+    let compressed = body_bytes(response).await;
+    let mut html = String::new();
+    flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut html).unwrap();
+    assert_eq!(html, "<p>Hello, world!");
+}
+
+#[tokio::test]
+async fn identity_only_clients_get_plain_html() {
+    let response = common::call(&mut app(), accepting("identity")).await;
+    assert!(!response.headers().contains_key(header::CONTENT_ENCODING));
+    assert_eq!(body_bytes(response).await, "<p>Hello, world!");
+}
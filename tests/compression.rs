@@ -0,0 +1,65 @@
+//! The minifier layered with `tower_http`'s `CompressionLayer`, in either order.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::io::Read;
+
+use axum::{
+    body::Body,
+    http::{header, Request},
+    response::Html,
+    routing::get,
+    Router,
+};
+use axum_mini::{minify_html_bytes, HtmlMinifierLayer, MinifierConfig};
+use common::body_bytes;
+use flate2::read::GzDecoder;
+use tower_http::compression::CompressionLayer;
+
+/// A page that is still worth compressing once minified.
+fn page() -> String {
+    format!("<ul>\n{}</ul>\n", "  <li>  An item worth listing  </li>\n".repeat(20))
+}
+
+fn gzip_request() -> Request<Body> {
+    Request::builder()
+        .uri("/")
+        .header(header::ACCEPT_ENCODING, "gzip")
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn gunzip(compressed: &[u8]) -> String {
+    let mut out = String::new();
+    GzDecoder::new(compressed).read_to_string(&mut out).unwrap();
+    out
+}
+
+#[tokio::test]
+async fn minifier_inside_compression_sends_minified_gzip() {
+    // The layer added last wraps the others, so the minifier sees the response first
+    let mut app = Router::new()
+        .route("/", get(|| async { Html(page()) }))
+        .layer(HtmlMinifierLayer::new())
+        .layer(CompressionLayer::new());
+
+    let response = common::call(&mut app, gzip_request()).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    let minified = minify_html_bytes(page().as_bytes(), &MinifierConfig::default());
+    assert_eq!(gunzip(&body_bytes(response).await).as_bytes(), minified);
+}
+
+#[tokio::test]
+async fn minifier_outside_compression_forwards_gzip_untouched() {
+    let mut app = Router::new()
+        .route("/", get(|| async { Html(page()) }))
+        .layer(CompressionLayer::new())
+        .layer(HtmlMinifierLayer::new());
+
+    // Compressed bytes are skipped rather than mangled, so the page arrives intact
+    let response = common::call(&mut app, gzip_request()).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(gunzip(&body_bytes(response).await), page());
+}
@@ -0,0 +1,100 @@
+//! Which responses are recognized as minifiable.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, HeaderName, Response},
+};
+use axum_mini::{minify_response, ContentTypeClass, MinifierConfig};
+use common::{body_bytes, with_type};
+
+const PAGE: &str = "<p>  Hello  </p>";
+
+/// The body of a response of `content_type` after the minifier, with `cfg`.
+async fn minified_as(content_type: &str, body: &'static str, cfg: &MinifierConfig) -> String {
+    let response = minify_response(with_type(content_type, body), cfg).await;
+    String::from_utf8(body_bytes(response).await.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn content_type_matching_ignores_case_and_parameters() {
+    let cfg = MinifierConfig::default();
+    assert_eq!(minified_as("Text/HTML; charset=UTF-8", PAGE, &cfg).await, "<p>Hello");
+    assert_eq!(minified_as("TEXT/HTML", PAGE, &cfg).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn content_type_containing_html_is_not_html() {
+    let cfg = MinifierConfig::default();
+    assert_eq!(minified_as("application/json; profile=\"text/html\"", PAGE, &cfg).await, PAGE);
+    assert_eq!(minified_as("text/htmlx", PAGE, &cfg).await, PAGE);
+    assert_eq!(minified_as("x-text/html", PAGE, &cfg).await, PAGE);
+}
+
+#[tokio::test]
+async fn svg_loses_indentation_and_comments_but_keeps_geometry() {
+    let svg = "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 24 24\">\n  <!-- the dot -->\n  <circle cx=\"12\" cy=\"12\" r=\"4.5\" fill=\"#FF0000\"/>\n  <path d=\"M 2 2 L 22 22\"/>\n</svg>\n";
+    let cfg = MinifierConfig::builder().minify_xml(true).build();
+
+    let minified = minified_as("image/svg+xml", svg, &cfg).await;
+    assert_eq!(
+        minified,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 24 24\"><circle cx=\"12\" cy=\"12\" r=\"4.5\" fill=\"#FF0000\"/><path d=\"M 2 2 L 22 22\"/></svg>",
+    );
+    // Off by default
+    assert_eq!(minified_as("image/svg+xml", svg, &MinifierConfig::default()).await, svg);
+}
+
+#[tokio::test]
+async fn untyped_bodies_are_sniffed_only_when_enabled() {
+    let untyped = |body: &'static str| Response::new(Body::from(body));
+    let page = "<!DOCTYPE html>\n<html>  <p>  Hello  </p></html>";
+    let sniff = MinifierConfig::builder().sniff_html(true).build();
+
+    let response = minify_response(untyped(page), &sniff).await;
+    assert_eq!(body_bytes(response).await, "<!doctype html><p>Hello");
+
+    let response = minify_response(untyped("Hello  <p>  world  </p>"), &sniff).await;
+    assert_eq!(body_bytes(response).await, "Hello  <p>  world  </p>");
+
+    let response = minify_response(untyped(page), &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn each_content_type_uses_its_own_settings() {
+    let cfg = MinifierConfig::builder()
+        .minify_css_responses(true)
+        .content_type_config(ContentTypeClass::Html, MinifierConfig::builder().keep_comments(true).build())
+        .build();
+
+    let page = "<p>  Hello  </p>  <!-- build 42 -->";
+    assert_eq!(minified_as("text/html", page, &cfg).await, "<p>Hello<!-- build 42 -->");
+    let css = "a  {  color: red  }  /* build 42 */";
+    assert_eq!(minified_as("text/css", css, &cfg).await, "a{color:red}");
+
+    // Without the override, HTML comments go too
+    let cfg = MinifierConfig::default();
+    assert_eq!(minified_as("text/html", page, &cfg).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn fragments_are_collapsed_around_preserved_regions() {
+    let fragment = "\n  <li>  One  </li>\n  <li><code>  a   b  </code></li>\n";
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header("x-fragment", "1")
+        .body(Body::from(fragment))
+        .unwrap();
+    let cfg = MinifierConfig::builder()
+        .fragment_header(HeaderName::from_static("x-fragment"))
+        .preserve_sensitive_whitespace(true)
+        .build();
+
+    let response = minify_response(response, &cfg).await;
+    assert!(!response.headers().contains_key("x-fragment"));
+    assert_eq!(body_bytes(response).await, "<li> One </li>\n<li><code>  a   b  </code></li>");
+}
@@ -0,0 +1,82 @@
+//! The minifier core, used without axum or an async runtime.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use axum_mini::{minify_html_bytes, minify_in_place, MinifierConfig, StreamingMinifier};
+use bytes::Bytes;
+use http_body::{Body, Frame};
+
+/// A body yielding `chunks` one frame at a time, always ready.
+struct Chunks(VecDeque<&'static str>);
+
+impl Body for Chunks {
+    type Data = Bytes;
+    type Error = std::convert::Infallible;
+
+    fn poll_frame(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        Poll::Ready(self.0.pop_front().map(|chunk| Ok(Frame::data(Bytes::from_static(chunk.as_bytes())))))
+    }
+}
+
+#[test]
+fn minifies_without_a_runtime() {
+    let minified = minify_html_bytes(b"<ul>\n  <li>  One  </li>\n</ul>", &MinifierConfig::default());
+    assert_eq!(minified, b"<ul><li>One</ul>");
+}
+
+#[test]
+fn minifies_in_place_without_a_runtime() {
+    let mut html = b"<p>  Hello,   world!  </p>".to_vec();
+    minify_in_place(&mut html, &MinifierConfig::default());
+    assert_eq!(html, b"<p>Hello, world!");
+}
+
+/// Everything `body` yields, polled without a runtime.
+fn streamed(mut body: StreamingMinifier<Chunks>) -> String {
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut out = Vec::new();
+    while let Poll::Ready(Some(frame)) = Pin::new(&mut body).poll_frame(&mut cx) {
+        out.extend_from_slice(&frame.unwrap().into_data().unwrap());
+    }
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn streams_without_a_runtime() {
+    let body = StreamingMinifier::new(Chunks(VecDeque::from(["<p>  Hello ", "  world  </p>\n\n", "<pre>  a  </pre>"])));
+    assert_eq!(streamed(body), "<p> Hello world </p>\n<pre>  a  </pre>");
+}
+
+#[test]
+fn streaming_keeps_the_regions_the_config_preserves() {
+    // Markers and closing tags are split across frames
+    let chunks = [
+        "<p>  a  </p>\n<!--minify:",
+        "off-->  <b>  kept  </b>  <!--minify:on-->  <p>  b  <code>  x   y </co",
+        "de>  <noscript>  n  </noscript>  <my-pre>  z  </MY-PRE>",
+    ];
+    let cfg = MinifierConfig::builder()
+        .honor_no_minify_markers(true)
+        .preserve_sensitive_whitespace(true)
+        .minify_noscript(false)
+        .preserve_whitespace_tags(["my-pre"])
+        .build();
+
+    let body = StreamingMinifier::with_config(Chunks(VecDeque::from(chunks)), &cfg);
+    assert_eq!(
+        streamed(body),
+        "<p> a </p>\n<!--minify:off-->  <b>  kept  </b>  <!--minify:on--> <p> b <code>  x   y </code> \
+         <noscript>  n  </noscript> <my-pre>  z  </MY-PRE>",
+    );
+
+    let body = StreamingMinifier::new(Chunks(VecDeque::from(chunks)));
+    assert_eq!(
+        streamed(body),
+        "<p> a </p>\n<!--minify:off--> <b> kept </b> <!--minify:on--> <p> b <code> x y </code> <noscript> n </noscript> \
+         <my-pre> z </MY-PRE>",
+    );
+}
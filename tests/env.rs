@@ -0,0 +1,29 @@
+//! Settings read from the environment.
+//!
+//! The only test in its own binary, so nothing else reads the environment while it is changed.
+
+use axum_mini::MinifierConfig;
+
+#[test]
+fn from_env_reads_switches_and_sizes() {
+    // SAFETY: this is the only test in the process
+    unsafe {
+        std::env::set_var("MINIFY_HTML", "OFF");
+        std::env::set_var("MINIFY_HTML_MIN_SIZE", "1024");
+        std::env::set_var("MINIFY_HTML_KEEP_COMMENTS", "not a flag");
+    }
+    let config = MinifierConfig::from_env();
+    assert!(!config.is_enabled());
+    assert_eq!(config.min_size_bytes, 1024);
+    assert!(!config.keep_comments);
+
+    unsafe {
+        std::env::set_var("MINIFY_HTML", " yes ");
+        std::env::remove_var("MINIFY_HTML_MIN_SIZE");
+        std::env::set_var("MINIFY_HTML_KEEP_COMMENTS", "True");
+    }
+    let config = MinifierConfig::from_env();
+    assert!(config.is_enabled());
+    assert_eq!(config.min_size_bytes, 0);
+    assert!(config.keep_comments);
+}
@@ -0,0 +1,107 @@
+//! The `tracing` events the middleware emits.
+
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use axum::{response::Html, routing::get, Router};
+use axum_mini::HtmlMinifierLayer;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Instrument, Metadata, Subscriber,
+};
+
+/// An event as recorded by [`Recorder`].
+#[derive(Debug, Default)]
+struct Recorded {
+    /// Field names and their values, formatted with `Debug`.
+    fields: Vec<(String, String)>,
+    /// The name of the span the event happened in.
+    span: Option<&'static str>,
+}
+
+impl Recorded {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, value)| value.as_str())
+    }
+}
+
+impl Visit for Recorded {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields.push((field.name().to_owned(), format!("{value:?}")));
+    }
+}
+
+/// A subscriber keeping every event, and which span it happened in.
+#[derive(Default)]
+struct Recorder {
+    next_id: AtomicU64,
+    names: Mutex<Vec<&'static str>>,
+    entered: Mutex<Vec<u64>>,
+    events: Arc<Mutex<Vec<Recorded>>>,
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        self.names.lock().unwrap().push(span.metadata().name());
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut recorded = Recorded::default();
+        event.record(&mut recorded);
+        let current = self.entered.lock().unwrap().last().copied();
+        recorded.span = current.map(|id| self.names.lock().unwrap()[id as usize - 1]);
+        self.events.lock().unwrap().push(recorded);
+    }
+
+    fn enter(&self, span: &span::Id) {
+        self.entered.lock().unwrap().push(span.into_u64());
+    }
+
+    fn exit(&self, span: &span::Id) {
+        let mut entered = self.entered.lock().unwrap();
+        if let Some(i) = entered.iter().rposition(|&id| id == span.into_u64()) {
+            entered.remove(i);
+        }
+    }
+}
+
+#[tokio::test]
+async fn minified_event_carries_request_fields_in_the_request_span() {
+    let recorder = Recorder::default();
+    let events = recorder.events.clone();
+    let _guard = tracing::subscriber::set_default(recorder);
+
+    let mut app = Router::new()
+        .route("/page", get(|| async { Html("<p>  Hello  </p>") }))
+        .layer(HtmlMinifierLayer::new());
+    common::get(&mut app, "/page?draft=1").instrument(tracing::info_span!("request")).await;
+
+    let events = events.lock().unwrap();
+    let event = events
+        .iter()
+        .find(|event| event.field("message") == Some("minified response"))
+        .expect("no minified response event");
+    assert_eq!(event.field("method"), Some("GET"));
+    assert_eq!(event.field("path"), Some("/page"));
+    assert_eq!(event.field("original_len"), Some("16"));
+    assert_eq!(event.field("minified_len"), Some("8"));
+    assert_eq!(event.span, Some("request"));
+}
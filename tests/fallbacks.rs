@@ -0,0 +1,71 @@
+//! Minifications that fail, and what is sent instead.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::{
+    sync::{mpsc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::StatusCode,
+};
+use axum_mini::{minify_response, minify_stream, MinifierConfig, MinifyError, MinifyStats};
+use common::{body_bytes, html};
+
+#[tokio::test]
+async fn panicking_minifier_sends_original() {
+    let page = "<p>  Hello  </p>";
+    let cfg = MinifierConfig::builder().minify_fn(|_, _| panic!("minifier bug")).build();
+
+    let response = minify_response(html(page), &cfg).await;
+    assert!(response.status().is_success());
+    assert!(!response.extensions().get::<MinifyStats>().unwrap().minified);
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn slow_minifier_times_out_to_original() {
+    let page = "<p>  Hello  </p>";
+    // The minifier blocks until the test has its response, so it can't finish in time
+    let (release, released) = mpsc::channel::<()>();
+    let released = Mutex::new(released);
+    let cfg = MinifierConfig::builder()
+        .timeout(Duration::from_millis(10))
+        .minify_fn(move |input, _| {
+            let _ = released.lock().unwrap().recv();
+            input.to_vec()
+        })
+        .build();
+
+    let response = minify_response(html(page), &cfg).await;
+    release.send(()).unwrap();
+    assert!(response.status().is_success());
+    assert!(!response.extensions().get::<MinifyStats>().unwrap().minified);
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn read_errors_reach_stream_callers_and_answer_responses() {
+    let broken = || futures_util::stream::iter([Ok(Bytes::from("<p>")), Err(std::io::Error::other("reset"))]);
+
+    let err = minify_stream(broken(), &MinifierConfig::default()).await.unwrap_err();
+    assert!(matches!(err, MinifyError::BodyRead(_)));
+
+    // The configured page is for responses; a stream's caller still hears of the failure
+    let cfg = MinifierConfig::builder().read_error_message("Something went wrong").build();
+    let err = minify_stream(broken(), &cfg).await.unwrap_err();
+    assert!(matches!(err, MinifyError::BodyRead(_)));
+
+    let failing = html(Body::from_stream(broken()));
+    let response = minify_response(failing, &MinifierConfig::default()).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    let failing = html(Body::from_stream(broken()));
+    let response = minify_response(failing, &cfg).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body_bytes(response).await, "Something went wrong");
+}
@@ -0,0 +1,108 @@
+//! Headers of minified responses.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use axum::{
+    body::Body,
+    http::{header, Response, StatusCode},
+    response::Html,
+    routing::get,
+    Router,
+};
+use axum_mini::{minify_response, HtmlMinifierLayer, MinifierConfig};
+use futures_util::stream;
+use common::body_bytes;
+
+#[tokio::test]
+async fn content_length_matches_minified_body() {
+    let page = "<p>   Hello   </p>  ";
+    let mut app = Router::new()
+        .route("/", get(move || async move { ([(header::CONTENT_LENGTH, page.len())], Html(page)) }))
+        .layer(HtmlMinifierLayer::new());
+
+    let response = common::get(&mut app, "/").await;
+    let content_length = response.headers()[header::CONTENT_LENGTH].clone();
+    let body = body_bytes(response).await;
+    assert_eq!(body, "<p>Hello");
+    assert_eq!(content_length, body.len().to_string());
+}
+
+/// The 64-bit FNV-1a hash the middleware tags bodies with.
+fn fnv1a(body: &[u8]) -> u64 {
+    body.iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+#[tokio::test]
+async fn etag_is_recomputed_from_minified_body() {
+    let mut app = Router::new()
+        .route("/", get(|| async { ([(header::ETAG, "\"v1\"")], Html("<p>  Hello  </p>")) }))
+        .layer(HtmlMinifierLayer::with_config(MinifierConfig::builder().recompute_etag(true).build()));
+
+    let response = common::get(&mut app, "/").await;
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_owned();
+    let body = body_bytes(response).await;
+    assert_ne!(etag, "\"v1\"");
+    assert_eq!(etag, format!("W/\"{:x}-{:016x}\"", body.len(), fnv1a(&body)));
+}
+
+#[tokio::test]
+async fn chunked_response_gets_consistent_framing() {
+    let chunks = ["<ul>\n  <li>  One", "  </li>\n  <li>Two", "</li>\n</ul>\n"];
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .body(Body::from_stream(stream::iter(chunks.map(Ok::<_, std::io::Error>))))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert!(!response.headers().contains_key(header::TRANSFER_ENCODING));
+    assert_eq!(response.headers().get_all(header::CONTENT_LENGTH).iter().count(), 1);
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "23");
+    assert_eq!(body_bytes(response).await, "<ul><li>One<li>Two</ul>");
+}
+
+#[tokio::test]
+async fn empty_responses_keep_their_headers() {
+    let response = Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header(header::CONTENT_TYPE, "text/html")
+        .body(Body::empty())
+        .unwrap();
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+
+    let response = minify_response(common::html(Body::empty()), &MinifierConfig::default()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!response.headers().contains_key(header::CONTENT_LENGTH));
+    assert!(body_bytes(response).await.is_empty());
+}
+
+#[tokio::test]
+async fn untouched_headers_keep_their_order_and_count() {
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::SET_COOKIE, "session=abc; HttpOnly")
+        .header(header::SET_COOKIE, "theme=dark; Path=/")
+        .body(Body::from("<p>  Hello  </p>"))
+        .unwrap();
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    let cookies: Vec<_> = response.headers().get_all(header::SET_COOKIE).iter().collect();
+    assert_eq!(cookies, ["session=abc; HttpOnly", "theme=dark; Path=/"]);
+}
+
+#[tokio::test]
+async fn conflicting_framing_is_forwarded_untouched() {
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::TRANSFER_ENCODING, "chunked")
+        .header(header::CONTENT_LENGTH, "16")
+        .body(Body::from("<p>  Hello  </p>"))
+        .unwrap();
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "16");
+    assert_eq!(body_bytes(response).await, "<p>  Hello  </p>");
+}
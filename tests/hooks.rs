@@ -0,0 +1,76 @@
+//! User-supplied functions the pipeline calls.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    http::{header, HeaderValue},
+    response::Html,
+    routing::get,
+    Router,
+};
+use axum_mini::{HtmlMinifierLayer, MinifierConfig};
+use common::body_bytes;
+
+#[tokio::test]
+async fn custom_minifier_only_runs_on_html() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let cfg = MinifierConfig::builder()
+        .minify_css_responses(true)
+        .minify_fn(move |input, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            input.to_ascii_uppercase()
+        })
+        .build();
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>Hello</p>") }))
+        .route("/text", get(|| async { "plain text" }))
+        .route("/style.css", get(|| async { ([(header::CONTENT_TYPE, "text/css")], "a  {  color: red  }") }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    assert_eq!(body_bytes(common::get(&mut app, "/").await).await, "<P>HELLO</P>");
+    assert_eq!(body_bytes(common::get(&mut app, "/text").await).await, "plain text");
+    assert_eq!(body_bytes(common::get(&mut app, "/style.css").await).await, "a{color:red}");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn post_process_appends_bytes_and_sets_a_header() {
+    let cfg = MinifierConfig::builder()
+        .post_process(|body, headers| {
+            headers.insert("x-build", HeaderValue::from_static("abc123"));
+            [&body[..], b"<!-- abc123 -->"].concat().into()
+        })
+        .build();
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    let response = common::get(&mut app, "/").await;
+    assert_eq!(response.headers()["x-build"], "abc123");
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "23");
+    assert_eq!(body_bytes(response).await, "<p>Hello<!-- abc123 -->");
+}
+
+#[tokio::test]
+async fn pre_process_output_is_minified_and_only_runs_on_html() {
+    let cfg = MinifierConfig::builder()
+        .minify_css_responses(true)
+        .pre_process(|body| [&body[..], b"\n<footer>\n  <p>  Built   by us  </p>\n</footer>\n"].concat().into())
+        .build();
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<main>\n  <p>  Hello  </p>\n</main>") }))
+        .route("/style.css", get(|| async { ([(header::CONTENT_TYPE, "text/css")], "a  {  color: red  }") }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    let response = common::get(&mut app, "/").await;
+    assert_eq!(body_bytes(response).await, "<main><p>Hello</main><footer><p>Built by us</footer>");
+    assert_eq!(body_bytes(common::get(&mut app, "/style.css").await).await, "a{color:red}");
+}
@@ -0,0 +1,70 @@
+//! Streaming minification with `lol_html`, checked against the buffered minifier.
+
+#![cfg(all(feature = "axum", feature = "lol_html"))]
+
+mod common;
+
+use axum::body::{Body, Bytes};
+use axum_mini::{minify_html_bytes, minify_response, LolHtmlMinifier, MinifierConfig, MinifyMode};
+use common::{body_bytes, html};
+use http_body_util::BodyExt;
+
+async fn streamed(body: LolHtmlMinifier<Body>) -> Bytes {
+    body.collect().await.unwrap().to_bytes()
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle.as_bytes())
+}
+
+#[tokio::test]
+async fn verbatim_elements_are_kept_like_the_buffered_minifier_keeps_them() {
+    let script = "<script>\n  let  greeting = 'a   b';\n</script>";
+    let style = "<style>\n  p  {  color:  red  }\n</style>";
+    let pre = "<pre>  kept\n     as  is  </pre>";
+    let textarea = "<textarea>  typed\n    text  </textarea>";
+    let page = format!(
+        "<div>\n  <p>  Intro  </p>\n  {script}\n  {style}\n  <p>  Code:  </p>\n  {pre}\n  {textarea}\n</div>\n"
+    );
+    let cfg = MinifierConfig::default();
+
+    let streamed = streamed(LolHtmlMinifier::new(Body::from(page.clone()))).await;
+    let buffered = minify_html_bytes(page.as_bytes(), &cfg);
+    for region in [script, style, pre, textarea] {
+        assert!(contains(&streamed, region), "{region} was changed");
+    }
+    for region in [pre, textarea] {
+        assert!(contains(&buffered, region), "{region} was changed by the buffered minifier");
+    }
+    // Only the text around them was collapsed, and the rest of the minification still applies
+    assert!(streamed.len() < page.len());
+    assert_eq!(minify_html_bytes(&streamed, &cfg), buffered);
+}
+
+#[tokio::test]
+async fn middleware_keeps_the_regions_the_config_preserves() {
+    let marked = "<!--minify:off-->  <b>  kept  </b>  <!--minify:on-->";
+    let code = "<code>  x   y </code>";
+    let noscript = "<noscript>  n  </noscript>";
+    let custom = "<my-pre>  z  </my-pre>";
+    let page = format!("<p>  a  </p>\n{marked}  <p>  b  {code}  {noscript}  {custom}  </p>\n");
+    let cfg = MinifierConfig::builder()
+        .mode(MinifyMode::LolHtml)
+        .honor_no_minify_markers(true)
+        .preserve_sensitive_whitespace(true)
+        .minify_noscript(false)
+        .preserve_whitespace_tags(["my-pre"])
+        .build();
+
+    let body = body_bytes(minify_response(html(page.clone()), &cfg).await).await;
+    for region in [marked, code, noscript, custom] {
+        assert!(contains(&body, region), "{region} was changed");
+    }
+    assert!(body.len() < page.len());
+
+    // Without the config, they are collapsed like any other text
+    let body = streamed(LolHtmlMinifier::new(Body::from(page.clone()))).await;
+    for region in [marked, code, custom] {
+        assert!(!contains(&body, region), "{region} was kept");
+    }
+}
@@ -0,0 +1,104 @@
+//! What the minifier does to particular markup.
+
+use axum_mini::{minify_html_bytes, MinifierConfig};
+
+/// Minifies `html` with `cfg`, as text.
+fn minify(html: &str, cfg: &MinifierConfig) -> String {
+    String::from_utf8(minify_html_bytes(html.as_bytes(), cfg)).unwrap()
+}
+
+#[test]
+fn pre_whitespace_survives() {
+    let pre = "<pre>  fn main() {\n      println!(\"hi\");\n  }\n\n</pre>";
+    let html = format!("<div>\n  {pre}\n</div>");
+    assert_eq!(minify(&html, &MinifierConfig::default()), format!("<div>{pre}</div>"));
+
+    let cfg = MinifierConfig::builder().preserve_sensitive_whitespace(true).build();
+    let code = "<p>Run <code>cargo   test  --doc</code>\n  now</p>";
+    assert_eq!(minify(code, &cfg), "<p>Run <code>cargo   test  --doc</code> now");
+}
+
+#[test]
+fn marked_json_ld_is_preserved_exactly() {
+    let json_ld = "\n<script type=\"application/ld+json\">\n  {\n    \"@context\": \"https://schema.org\",\n    \"@type\":   \"Organization\"\n  }\n</script>\n";
+    let html = format!("<div>\n  <!--minify:off-->{json_ld}<!--minify:on-->\n  <p>  Acme  </p>\n</div>");
+    let cfg = MinifierConfig::builder().honor_no_minify_markers(true).build();
+
+    // The markers go, like any other comment
+    assert_eq!(minify(&html, &cfg), format!("<div>{json_ld}<p>Acme</div>"));
+}
+
+#[test]
+fn class_whitespace_is_collapsed() {
+    let cfg = MinifierConfig::builder().collapse_class_whitespace(true).build();
+    assert_eq!(minify(r#"<p class="  foo    bar ">x</p>"#, &cfg), r#"<p class="foo bar">x"#);
+    assert_eq!(minify("<p class=\"\tfoo\n bar\">x</p>", &cfg), r#"<p class="foo bar">x"#);
+
+    // Also with an engine that leaves attributes alone
+    let cfg = MinifierConfig::builder().collapse_class_whitespace(true).minify_fn(|input, _| input.to_vec()).build();
+    assert_eq!(minify(r#"<p class="  foo    bar ">x</p>"#, &cfg), r#"<p class="foo bar">x</p>"#);
+}
+
+#[test]
+fn inline_styles_are_compacted() {
+    // With CSS minification off, only the dedicated pass touches `style` attributes
+    let html = "<p style=\"color: #FFFFFF ; margin : 0px;\">x</p>";
+    let cfg = MinifierConfig::builder().minify_css(false).minify_inline_styles(true).build();
+    assert_eq!(minify(html, &cfg), "<p style=\"color:#ffffff;margin:0px\">x");
+    let untouched = MinifierConfig::builder().minify_css(false).build();
+    assert_eq!(minify(html, &untouched), "<p style=\"color: #FFFFFF ; margin : 0px;\">x");
+
+    // Quoted URLs and data URIs inside a value are left alone
+    let html = "<p style='background: url(\"data:image/png;base64,iVB OR\")  ; '>x</p>";
+    assert_eq!(minify(html, &cfg), "<p style='background:url(\"data:image/png;base64,iVB OR\")'>x");
+}
+
+#[test]
+fn redundant_type_attributes_are_removed() {
+    let html = "<script type=\"text/javascript\">go()</script><style type=\"text/css\">a{}</style>\
+        <script type=\"module\">go()</script><script type=\"application/ld+json\">{}</script>";
+    // A minifier that changes nothing, so that only the dedicated pass can drop them
+    let keep = MinifierConfig::builder().minify_fn(|input, _| input.to_vec());
+    let cfg = keep.clone().remove_redundant_type_attrs(true).build();
+
+    assert_eq!(
+        minify(html, &cfg),
+        "<script>go()</script><style>a{}</style>\
+         <script type=\"module\">go()</script><script type=\"application/ld+json\">{}</script>",
+    );
+    assert_eq!(minify(html, &keep.build()), html);
+}
+
+#[test]
+fn large_bodies_keep_preserved_regions_when_only_collapsed() {
+    let html = "<p>  a  </p>  <!--minify:off-->  <b>  kept  </b>  <!--minify:on-->  \
+        <code>  x   y </code>  <noscript>  <p>  n  </p>  </noscript>  <my-pre>  z  </my-pre>  \
+        <script nonce=\"\">go()</script>  <img src=\"data:image/png;base64,iVB  OR\">";
+    let cfg = MinifierConfig::builder()
+        .full_minify_max_bytes(64)
+        .honor_no_minify_markers(true)
+        .preserve_sensitive_whitespace(true)
+        .minify_noscript(false)
+        .preserve_whitespace_tags(["my-pre"])
+        .preserve_nonces(true)
+        .preserve_data_uris(true)
+        .build();
+
+    assert_eq!(
+        minify(html, &cfg),
+        "<p> a </p> <!--minify:off-->  <b>  kept  </b>  <!--minify:on--> \
+         <code>  x   y </code> <noscript>  <p>  n  </p>  </noscript> <my-pre>  z  </my-pre> \
+         <script nonce=\"\">go()</script> <img src=\"data:image/png;base64,iVB  OR\">",
+    );
+}
+
+#[test]
+fn template_placeholders_in_event_handlers_survive() {
+    let cfg = MinifierConfig::builder().minify_inline_event_handlers(true).build();
+    let mustache = r#"<button onclick="save( '{{ item.id }}' ) ;">Save</button>"#;
+    assert_eq!(minify(mustache, &cfg), r#"<button onclick="save(`{{ item.id }}`)">Save</button>"#);
+
+    // As a template literal the placeholder would need escaping, so the handler is kept
+    let interpolated = r#"<a onclick="track( '${event.name}' , 1 ) ;">Go</a>"#;
+    assert_eq!(minify(interpolated, &cfg), interpolated);
+}
@@ -0,0 +1,87 @@
+//! The counters and histogram recorded through the `metrics` facade.
+
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{response::Html, routing::get, Router};
+use axum_mini::HtmlMinifierLayer;
+use metrics::{Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, SharedString, Unit};
+
+/// Counter totals, by name and labels.
+static COUNTERS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+/// How many values each histogram recorded, by name and labels.
+static HISTOGRAMS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// A metric written to the maps above, under its name followed by its labels.
+struct Named(String);
+
+impl Named {
+    fn new(key: &Key) -> Arc<Self> {
+        let labels: Vec<String> = key.labels().map(|label| format!("{}={}", label.key(), label.value())).collect();
+        Arc::new(Self(format!("{}{{{}}}", key.name(), labels.join(","))))
+    }
+}
+
+impl CounterFn for Named {
+    fn increment(&self, value: u64) {
+        *COUNTERS.lock().unwrap().entry(self.0.clone()).or_default() += value;
+    }
+
+    fn absolute(&self, value: u64) {
+        COUNTERS.lock().unwrap().insert(self.0.clone(), value);
+    }
+}
+
+impl HistogramFn for Named {
+    fn record(&self, _: f64) {
+        *HISTOGRAMS.lock().unwrap().entry(self.0.clone()).or_default() += 1;
+    }
+}
+
+struct Recorder;
+
+impl metrics::Recorder for Recorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        Counter::from_arc(Named::new(key))
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::from_arc(Named::new(key))
+    }
+}
+
+#[tokio::test]
+async fn html_minifications_increment_the_counters() {
+    assert!(metrics::set_global_recorder(Recorder).is_ok());
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+        .route("/text", get(|| async { "plain  text" }))
+        .layer(HtmlMinifierLayer::new());
+
+    common::get(&mut app, "/").await;
+    common::get(&mut app, "/").await;
+    common::get(&mut app, "/text").await;
+
+    let counters = COUNTERS.lock().unwrap().clone();
+    assert_eq!(counters["axum_mini_original_bytes_total{content_type=html}"], 32);
+    assert_eq!(counters["axum_mini_minified_bytes_total{content_type=html}"], 16);
+    assert_eq!(counters.len(), 2);
+    let histograms = HISTOGRAMS.lock().unwrap().clone();
+    assert_eq!(histograms["axum_mini_minify_duration_seconds{content_type=html}"], 2);
+}
@@ -0,0 +1,69 @@
+//! The `minify_html` settings each preset minifies with.
+
+use std::sync::{Arc, Mutex};
+
+use axum_mini::{minify_html_bytes, Cfg, MinifierConfig};
+
+/// The `Cfg` that `config` hands the minifier for a small document.
+fn cfg_used(config: MinifierConfig) -> Cfg {
+    let seen = Arc::new(Mutex::new(None));
+    let recorder = seen.clone();
+    let config = MinifierConfig {
+        minify_fn: Some(Arc::new(move |input: &[u8], cfg: &Cfg| {
+            *recorder.lock().unwrap() = Some(cfg.clone());
+            input.to_vec()
+        })),
+        ..config
+    };
+    minify_html_bytes(b"<!doctype html><p>Hello</p>", &config);
+    let cfg = seen.lock().unwrap().take();
+    cfg.expect("the minifier was not called")
+}
+
+/// The `Cfg` settings the presets differ in.
+fn flags(cfg: &Cfg) -> [bool; 7] {
+    [
+        cfg.keep_comments,
+        cfg.minify_css,
+        cfg.minify_js,
+        cfg.remove_bangs,
+        cfg.remove_processing_instructions,
+        cfg.allow_removing_spaces_between_attributes,
+        cfg.keep_closing_tags,
+    ]
+}
+
+#[test]
+fn conservative_keeps_comments_and_embedded_code() {
+    let cfg = cfg_used(MinifierConfig::conservative());
+    assert!(cfg.keep_comments);
+    assert!(!cfg.minify_css);
+    assert!(!cfg.minify_js);
+    assert!(!cfg.remove_bangs);
+    assert!(!cfg.remove_processing_instructions);
+    assert!(!cfg.allow_removing_spaces_between_attributes);
+}
+
+#[test]
+fn default_minifies_everything_minify_html_can() {
+    let cfg = cfg_used(MinifierConfig::default());
+    assert!(!cfg.keep_comments);
+    assert!(cfg.minify_css);
+    assert!(cfg.minify_js);
+    assert!(cfg.remove_bangs);
+    assert!(cfg.remove_processing_instructions);
+    assert!(cfg.allow_removing_spaces_between_attributes);
+    assert!(!cfg.keep_closing_tags);
+}
+
+#[test]
+fn aggressive_adds_passes_on_top_of_the_default_cfg() {
+    let aggressive = cfg_used(MinifierConfig::aggressive());
+    let default = cfg_used(MinifierConfig::default());
+    assert_eq!(flags(&aggressive), flags(&default));
+
+    let config = MinifierConfig::aggressive();
+    assert!(config.collapse_class_whitespace);
+    assert!(config.minify_inline_styles);
+    assert!(config.minify_css_responses && config.minify_js_responses && config.minify_xml);
+}
@@ -0,0 +1,54 @@
+//! Request bodies minified before the handler sees them.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Method, Request},
+    middleware,
+    routing::post,
+    Router,
+};
+use axum_mini::{html_request_minifier_with, MinifierConfig};
+use common::body_bytes;
+
+/// A router echoing the body it receives, behind the request minifier with `cfg`.
+fn echo(cfg: MinifierConfig) -> Router {
+    Router::new()
+        .route("/", post(|body: Bytes| async move { body }))
+        .layer(middleware::from_fn_with_state(cfg, html_request_minifier_with))
+}
+
+/// What the handler receives for an HTML `body` sent with `headers`.
+async fn received(cfg: MinifierConfig, headers: &[(&str, &str)], body: &'static [u8]) -> Vec<u8> {
+    let mut req = Request::builder().method(Method::POST).uri("/").header(header::CONTENT_TYPE, "text/html");
+    for (name, value) in headers {
+        req = req.header(*name, *value);
+    }
+    let response = common::call(&mut echo(cfg), req.body(Body::from(body)).unwrap()).await;
+    body_bytes(response).await.to_vec()
+}
+
+#[tokio::test]
+async fn identity_encoded_requests_are_minified() {
+    let page = b"<p>  Hello  </p>";
+    let cfg = MinifierConfig::default;
+    assert_eq!(received(cfg(), &[("content-encoding", "identity")], page).await, b"<p>Hello");
+    assert_eq!(received(cfg(), &[("content-encoding", "gzip")], page).await, page);
+}
+
+#[tokio::test]
+async fn requests_get_the_response_checks() {
+    let page = b"<p>  Hello  </p>";
+    let small = MinifierConfig::builder().min_size_bytes(256).build();
+    assert_eq!(received(small, &[], page).await, page);
+
+    let skip = MinifierConfig::builder().skip_if(|parts, _| parts.headers.contains_key("x-raw")).build();
+    assert_eq!(received(skip.clone(), &[("x-raw", "1")], page).await, page);
+    assert_eq!(received(skip, &[], page).await, b"<p>Hello");
+
+    let binary: &[u8] = b"<p>  caf\xe9  \xff </p>";
+    assert_eq!(received(MinifierConfig::default(), &[], binary).await, binary);
+}
@@ -0,0 +1,87 @@
+//! Static files served by `tower_http`'s `ServeDir` behind the minifier.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use axum_mini::HtmlMinifierLayer;
+use common::body_bytes;
+use tower_http::services::ServeDir;
+
+const PAGE: &str = "<ul>\n  <li>  One  </li>\n</ul>\n";
+const STYLES: &str = "p {\n  color:  red;\n}\n";
+
+/// A directory unique to this test and process, removed again on drop.
+struct Site(PathBuf);
+
+impl Site {
+    fn new(test: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("axum_mini-{test}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), PAGE).unwrap();
+        std::fs::write(dir.join("app.css"), STYLES).unwrap();
+        Self(dir)
+    }
+
+    fn app(&self) -> Router {
+        Router::new().fallback_service(ServeDir::new(&self.0)).layer(HtmlMinifierLayer::new())
+    }
+}
+
+impl Drop for Site {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[tokio::test]
+async fn served_files_are_minified_with_consistent_headers() {
+    let site = Site::new("served-files");
+    let mut app = site.app();
+
+    let response = common::get(&mut app, "/index.html").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+    assert_eq!(response.headers()[header::ACCEPT_RANGES], "none");
+    let content_length = response.headers()[header::CONTENT_LENGTH].clone();
+    let body = body_bytes(response).await;
+    assert_eq!(body, "<ul><li>One</ul>");
+    assert_eq!(content_length, body.len().to_string());
+
+    // Other files are left as stored
+    let response = common::get(&mut app, "/app.css").await;
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], STYLES.len().to_string());
+    assert_eq!(body_bytes(response).await, STYLES);
+}
+
+#[tokio::test]
+async fn ranges_and_head_requests_describe_the_stored_file() {
+    let site = Site::new("ranges-and-head");
+    let mut app = site.app();
+
+    let request = Request::builder()
+        .uri("/index.html")
+        .header(header::RANGE, "bytes=0-6")
+        .body(Body::empty())
+        .unwrap();
+    let response = common::call(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(body_bytes(response).await, &PAGE[..7]);
+
+    let request = Request::builder()
+        .method(Method::HEAD)
+        .uri("/index.html")
+        .body(Body::empty())
+        .unwrap();
+    let response = common::call(&mut app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], PAGE.len().to_string());
+    assert!(body_bytes(response).await.is_empty());
+}
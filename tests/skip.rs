@@ -0,0 +1,330 @@
+//! Responses the middleware forwards without minifying them.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use std::{
+    convert::Infallible,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{header, Method, Request, Response, StatusCode},
+    response::Html,
+    routing::get,
+    Router,
+};
+use axum_mini::{minify_response, HtmlMinifierLayer, MinifierConfig, SkipReason};
+use common::{body_bytes, html};
+use futures_util::FutureExt;
+use http_body_util::BodyExt;
+
+#[tokio::test]
+async fn keeps_bodies_below_min_size() {
+    let page = "<p>   Hello   </p>  ";
+    assert_eq!(page.len(), 20);
+    let cfg = MinifierConfig::builder().min_size_bytes(256).build();
+
+    let response = minify_response(html(page), &cfg).await;
+    assert_eq!(body_bytes(response).await, page);
+
+    // The threshold is all that kept it as it was
+    let response = minify_response(html(page), &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn handler_opt_out_header_is_honored_and_removed() {
+    let page = "<p>  Hello  </p>";
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header("x-minify", "off")
+        .body(Body::from(page))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert!(!response.headers().contains_key("x-minify"));
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn compressed_bodies_pass_through() {
+    let gzipped: &[u8] = &[0x1f, 0x8b, 0x08, 0x00, b' ', b' ', b'\n', 0xff, 0x00, b'<', b'p', b'>'];
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(Body::from(gzipped))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    assert_eq!(body_bytes(response).await, gzipped);
+}
+
+#[tokio::test]
+async fn head_requests_are_not_minified() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let cfg = MinifierConfig::builder()
+        .minify_fn(move |input, cfg| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            minify_html::minify(input, cfg)
+        })
+        .build();
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    let head = Request::builder().method(Method::HEAD).uri("/").body(Body::empty()).unwrap();
+    common::call(&mut app, head).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    common::get(&mut app, "/").await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn error_pages_are_only_minified_when_enabled() {
+    let not_found = || async { (StatusCode::NOT_FOUND, Html("<p>  Not found  </p>")) };
+
+    let mut app = Router::new().route("/", get(not_found)).layer(HtmlMinifierLayer::new());
+    let response = common::get(&mut app, "/").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_bytes(response).await, "<p>  Not found  </p>");
+
+    let cfg = MinifierConfig::builder().minify_error_responses(true).build();
+    let mut app = Router::new().route("/", get(not_found)).layer(HtmlMinifierLayer::with_config(cfg));
+    let response = common::get(&mut app, "/").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_bytes(response).await, "<p>Not found");
+}
+
+#[tokio::test]
+async fn skipped_path_prefixes_are_not_minified() {
+    let page = || async { Html("<p>  Hello  </p>") };
+    let cfg = MinifierConfig::builder().skip_path_prefixes(["/admin/"]).build();
+    let mut app = Router::new()
+        .route("/admin/x", get(page))
+        .route("/home", get(page))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    assert_eq!(body_bytes(common::get(&mut app, "/admin/x").await).await, "<p>  Hello  </p>");
+    assert_eq!(body_bytes(common::get(&mut app, "/home").await).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn runtime_switch_toggles_minification() {
+    let enabled = Arc::new(AtomicBool::new(true));
+    let cfg = MinifierConfig::builder().enabled(enabled.clone()).build();
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    assert_eq!(body_bytes(common::get(&mut app, "/").await).await, "<p>Hello");
+    enabled.store(false, Ordering::Relaxed);
+    assert_eq!(body_bytes(common::get(&mut app, "/").await).await, "<p>  Hello  </p>");
+    enabled.store(true, Ordering::Relaxed);
+    assert_eq!(body_bytes(common::get(&mut app, "/").await).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn no_transform_is_respected() {
+    let page = "<p>  Hello  </p>";
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CACHE_CONTROL, "public, No-Transform")
+        .body(Body::from(page))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn invalid_utf8_passes_through() {
+    let body: &[u8] = b"<p>  caf\xe9  \xff\xfe </p>";
+    let response = minify_response(html(body), &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, body);
+}
+
+#[tokio::test]
+async fn attachments_pass_through() {
+    let page = "<html>\n  <body>  Report  </body>\n</html>\n";
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CONTENT_DISPOSITION, "Attachment; filename=\"report.html\"")
+        .body(Body::from(page))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn sample_rate_bounds_always_or_never_minify() {
+    let page = "<p>  Hello  </p>";
+    let never = MinifierConfig::builder().sample_rate(0.0).build();
+    let always = MinifierConfig::builder().sample_rate(1.0).build();
+
+    for _ in 0..100 {
+        assert_eq!(body_bytes(minify_response(html(page), &never).await).await, page);
+        assert_eq!(body_bytes(minify_response(html(page), &always).await).await, "<p>Hello");
+    }
+}
+
+#[tokio::test]
+async fn already_minified_bodies_skip_the_minifier() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let cfg = MinifierConfig::builder()
+        .skip_minified_below(0.01)
+        .minify_fn(move |input, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            input.to_vec()
+        })
+        .build();
+
+    let compact = "<!doctype html><html><head><title>Hi</title></head><body><p>Hello world</p></body></html>";
+    assert_eq!(body_bytes(minify_response(html(compact), &cfg).await).await, compact);
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let indented = "<html>\n  <body>\n    <p>Hello world</p>\n  </body>\n</html>\n";
+    minify_response(html(indented), &cfg).await;
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn bodies_over_the_limit_are_forwarded_or_rejected() {
+    let page = "<p>  Hello  </p>".repeat(8);
+    let forward = MinifierConfig::builder().max_body_bytes(64).build();
+    let reject = MinifierConfig::builder().max_body_bytes(64).reject_too_large(true).build();
+
+    let response = minify_response(html(page.clone()), &forward).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_bytes(response).await, page);
+
+    let response = minify_response(html(page.clone()), &reject).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // Bodies within the limit are still minified
+    let response = minify_response(html("<p>  Hello  </p>"), &reject).await;
+    assert_eq!(body_bytes(response).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn event_streams_are_not_buffered() {
+    // A body that never ends: collecting it would hang the test
+    let events = futures_util::stream::pending::<Result<Bytes, Infallible>>();
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .body(Body::from_stream(events))
+        .unwrap();
+    let cfg = MinifierConfig::builder().content_types(["text/html", "text/event-stream"]).build();
+
+    let response = minify_response(response, &cfg).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/event-stream");
+    let mut body = response.into_body();
+    assert!(body.frame().now_or_never().is_none());
+}
+
+#[tokio::test]
+async fn partial_content_passes_through() {
+    let part = "<p>  Hel";
+    let response = Response::builder()
+        .status(StatusCode::PARTIAL_CONTENT)
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CONTENT_RANGE, "bytes 0-7/16")
+        .header(header::CONTENT_LENGTH, part.len())
+        .body(Body::from(part))
+        .unwrap();
+
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 0-7/16");
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "8");
+    assert_eq!(body_bytes(response).await, part);
+
+    // A Content-Range alone is enough
+    let response = Response::builder()
+        .header(header::CONTENT_TYPE, "text/html")
+        .header(header::CONTENT_RANGE, "bytes 0-7/16")
+        .body(Body::from(part))
+        .unwrap();
+    let response = minify_response(response, &MinifierConfig::default()).await;
+    assert_eq!(body_bytes(response).await, part);
+}
+
+#[tokio::test]
+async fn html_files_under_download_prefixes_pass_through() {
+    let page = "<p>  Hello  </p>";
+    let mut app = Router::new()
+        .route("/downloads/page.html", get(move || async move { Html(page) }))
+        .route("/downloads/page", get(move || async move { Html(page) }))
+        .route("/page.html", get(move || async move { Html(page) }))
+        .layer(HtmlMinifierLayer::with_config(
+            MinifierConfig::builder().download_path_prefixes(["/downloads/"]).build(),
+        ));
+
+    assert_eq!(body_bytes(common::get(&mut app, "/downloads/page.html").await).await, page);
+    // Only `.html` and `.htm` files are downloads, and only under the prefix
+    assert_eq!(body_bytes(common::get(&mut app, "/downloads/page").await).await, "<p>Hello");
+    assert_eq!(body_bytes(common::get(&mut app, "/page.html").await).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn fallback_pages_follow_the_status_rules() {
+    let page = "<h1>  Not found  </h1>";
+    let router = || {
+        Router::new()
+            .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+            .fallback(move || async move { (StatusCode::NOT_FOUND, Html(page)) })
+    };
+
+    let mut app = router().layer(HtmlMinifierLayer::new());
+    let response = common::get(&mut app, "/missing").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_bytes(response).await, page);
+
+    let cfg = MinifierConfig::builder().minify_error_responses(true).build();
+    let mut app = router().layer(HtmlMinifierLayer::with_config(cfg));
+    let response = common::get(&mut app, "/missing").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_bytes(response).await, "<h1>Not found</h1>");
+
+    // A fallback answering `200` is minified like any other page
+    let mut app = Router::new().fallback(|| async { Html("<p>  Hello  </p>") }).layer(HtmlMinifierLayer::new());
+    assert_eq!(body_bytes(common::get(&mut app, "/anything").await).await, "<p>Hello");
+}
+
+#[tokio::test]
+async fn mismatched_content_length_passes_through_when_strict() {
+    let page = "<p>  Hello  </p>";
+    let wrong_length = || {
+        Response::builder()
+            .header(header::CONTENT_TYPE, "text/html")
+            .header(header::CONTENT_LENGTH, "99")
+            .body(Body::from(page))
+            .unwrap()
+    };
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let reasons = skipped.clone();
+    let cfg = MinifierConfig::builder()
+        .strict_content_length(true)
+        .on_skip(move |reason| reasons.lock().unwrap().push(reason))
+        .build();
+
+    let response = minify_response(wrong_length(), &cfg).await;
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "99");
+    assert_eq!(body_bytes(response).await, page);
+    assert_eq!(*skipped.lock().unwrap(), [SkipReason::LengthMismatch]);
+
+    // The check is opt-in; without it the body is minified and its length corrected
+    let response = minify_response(wrong_length(), &MinifierConfig::default()).await;
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], "8");
+    assert_eq!(body_bytes(response).await, "<p>Hello");
+}
@@ -0,0 +1,65 @@
+//! What the middleware reports about each response.
+
+#![cfg(feature = "axum")]
+
+mod common;
+
+use axum::{http::header, response::Html, routing::get, Router};
+use axum_mini::{HtmlMinifierLayer, MinifierConfig, MinifyStats};
+use common::body_bytes;
+
+#[tokio::test]
+async fn stats_extension_is_readable_after_the_middleware() {
+    let mut app = Router::new()
+        .route("/", get(|| async { Html("<p>  Hello  </p>") }))
+        .route("/text", get(|| async { "plain  text" }))
+        .layer(HtmlMinifierLayer::new());
+
+    let response = common::get(&mut app, "/").await;
+    let stats = *response.extensions().get::<MinifyStats>().unwrap();
+    assert_eq!(stats, MinifyStats { original_len: 16, final_len: 8, minified: true });
+    assert_eq!(stats.saved_bytes(), 8);
+    assert_eq!(stats.reduction_pct(), 50.0);
+
+    // Only minifiable bodies get stats
+    let response = common::get(&mut app, "/text").await;
+    assert!(response.extensions().get::<MinifyStats>().is_none());
+}
+
+#[tokio::test]
+async fn dry_run_sends_original_while_stats_show_savings() {
+    let page = "<head>\n  <link rel=stylesheet href=/app.css>\n</head>\n<p>  Hello  </p>\n";
+    let cfg = MinifierConfig::builder().dry_run(true).debug_headers(true).preload_links(4).build();
+    let mut app = Router::new()
+        .route("/", get(move || async move { ([(header::CONTENT_LENGTH, page.len())], Html(page)) }))
+        .layer(HtmlMinifierLayer::with_config(cfg));
+
+    let response = common::get(&mut app, "/").await;
+    let stats = *response.extensions().get::<MinifyStats>().unwrap();
+    assert_eq!(stats.original_len, page.len());
+    assert!(stats.minified && stats.final_len < stats.original_len);
+
+    // Neither the body nor the headers describing it were touched
+    assert_eq!(response.headers()[header::CONTENT_LENGTH], page.len().to_string());
+    assert!(!response.headers().contains_key("x-minified-bytes"));
+    assert!(!response.headers().contains_key(header::LINK));
+    assert_eq!(body_bytes(response).await, page);
+}
+
+#[tokio::test]
+async fn debug_headers_report_lengths_only_when_enabled() {
+    let page = "<p>  Hello  </p>";
+    let mut app = Router::new()
+        .route("/", get(move || async move { Html(page) }))
+        .layer(HtmlMinifierLayer::with_config(MinifierConfig::builder().debug_headers(true).build()));
+
+    let response = common::get(&mut app, "/").await;
+    assert_eq!(response.headers()["x-original-bytes"], "16");
+    assert_eq!(response.headers()["x-minified-bytes"], "8");
+    assert_eq!(body_bytes(response).await, "<p>Hello");
+
+    let mut app = Router::new().route("/", get(move || async move { Html(page) })).layer(HtmlMinifierLayer::new());
+    let response = common::get(&mut app, "/").await;
+    assert!(!response.headers().contains_key("x-original-bytes"));
+    assert!(!response.headers().contains_key("x-minified-bytes"));
+}